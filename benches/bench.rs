@@ -1,8 +1,11 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::f32::consts::PI;
+use clituner::bac::bitstream::Bitstream;
 use clituner::bac::imp;
 use clituner::bac::decl;
 use clituner::bac::conf;
+use clituner::bac::stream::StreamingBitstream;
+use clituner::bac::wide::{Simd, Wide};
 
 const FREQ: f32 = 261.626;
 const PERIOD: f32 = conf::CONFIG.samples_per_second as f32 / FREQ;
@@ -21,6 +24,14 @@ fn criterion_benchmark(c: &mut Criterion) {
     let signal = generate_input();
     c.bench_function("estimate_pitch_imperative", |b| b.iter(|| imp::Bitstream::estimate_pitch(black_box(&signal))));
     c.bench_function("estimate_pitch_declarative", |b| b.iter(|| decl::Bitstream::estimate_pitch(black_box(&signal))));
+    c.bench_function("estimate_pitch_wide", |b| b.iter(|| Bitstream::<Wide>::estimate_pitch(black_box(&signal))));
+    c.bench_function("estimate_pitch_simd", |b| b.iter(|| Bitstream::<Simd>::estimate_pitch(black_box(&signal))));
+
+    let mut stream = StreamingBitstream::<imp::Imperative>::new();
+    stream.push(&signal);
+    for hop in [32, 256, 512] {
+        c.bench_function(&format!("estimate_pitch_streaming_hop_{}", hop), |b| b.iter(|| stream.push(black_box(&signal[..hop]))));
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
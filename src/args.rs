@@ -0,0 +1,380 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use clituner::bac::conf::CONFIG;
+use clituner::error::Error;
+use clituner::note::parse_note;
+use clituner::preset::{Preset, PRESETS};
+use clituner::theme::{ColorChoice, Thresholds};
+use clituner::tone::Waveform;
+
+/// Lowest and highest A4 reference, from `--a4` or the keys.
+pub const REFERENCE_RANGE: (f32, f32) = (400.0, 480.0);
+
+pub const USAGE: &str = "\
+Usage: clituner [OPTIONS]
+       clituner tone <NOTE> [OPTIONS]
+
+Tone plays NOTE, such as A4 or Bb2, tuned to the same A4 reference until Enter is pressed.
+
+Options:
+    --format <FORMAT>       tui, or jsonl for one JSON object per reading on stdout [default: tui]
+    --compact               print a single updating line instead of the full screen display
+    --no-art                greet with plain text instead of the ASCII art banner
+    --color <WHEN>          auto, always or never [default: auto]
+    --in-tune <CENTS>       largest deviation still shown as in tune [default: 10]
+    --close <CENTS>         largest deviation shown as close rather than off [default: 30]
+    --a4 <HZ>               reference frequency of A4, 400 to 480 [default: 444]
+    --preset <NAME>         chromatic, guitar, bass, ukulele, violin, viola or cello [default: chromatic]
+    --history <SECONDS>     length of the pitch history graph, 0 to hide it [default: 10]
+    --record <PATH>         also write every reading to a CSV file
+    --append                add to the end of the CSV file rather than replacing it
+    --rotate <ROWS>         start a new CSV file, PATH.1, PATH.2 and so on, every ROWS readings
+    --midi-file <PATH>      write notes and pitch bend to a Standard MIDI File
+    --midi-port             send notes and pitch bend to a virtual ALSA sequencer port
+    --midi-channel <N>      MIDI channel from 1 to 16 [default: 1]
+    --bend-range <SEMIS>    pitch bend range the receiver is set up for [default: 2]
+    --osc <HOST:PORT>       send readings as OSC messages over UDP
+    --serve <ADDR>          serve a tuner page and live readings over HTTP, e.g. 127.0.0.1:8080
+    --intonation            check each string of the preset open against its 12th fret
+    --piano                 tune each key to a stretched target rather than equal temperament
+    --stretch <AMOUNT>      scale of the Railsback curve used until keys are measured [default: 1]
+    --drone                 play the preset's strings in turn while tuning, n skips to the next, h holds one
+    --drone-step <SECONDS>  time on each string before moving on, 0 to hold the first [default: 4]
+    --notch                 filter the drone out of the input so it isn't tuned to itself
+    --wave <WAVE>           tone and drone waveform, sine, triangle, sawtooth or pluck [default: sine]
+    --volume <LEVEL>        tone and drone volume from 0 to 1 [default: 0.5]
+    -h, --help              print this message
+";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayMode {
+    /// Full-screen ASCII art note glyphs.
+    Full,
+    /// One line rewritten in place, for status bars and small panes.
+    Compact
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Interactive terminal display.
+    Tui,
+    /// JSON Lines for scripts, without any terminal control codes.
+    Jsonl
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Args {
+    pub format: Format,
+    pub display: DisplayMode,
+    pub art: bool,
+    pub color: ColorChoice,
+    pub thresholds: Thresholds,
+    pub reference: f32,
+    /// Index into `PRESETS`.
+    pub preset: usize,
+    /// Seconds of pitch history to plot.
+    pub history: f32,
+    /// CSV file to record readings to.
+    pub record: Option<PathBuf>,
+    pub append: bool,
+    /// Rows per CSV file before moving on to the next.
+    pub rotate: Option<usize>,
+    pub midi_file: Option<PathBuf>,
+    pub midi_port: bool,
+    /// Zero based MIDI channel.
+    pub midi_channel: u8,
+    /// Pitch bend range in semitones.
+    pub bend_range: u8,
+    /// Where to send OSC messages to.
+    pub osc: Option<String>,
+    /// Address to serve the web page and readings on.
+    pub serve: Option<String>,
+    /// MIDI note to play instead of tuning.
+    pub tone: Option<i32>,
+    pub intonation: bool,
+    pub piano: bool,
+    /// Scale applied to the Railsback curve.
+    pub stretch: f32,
+    pub drone: bool,
+    /// Seconds the drone spends on each string.
+    pub drone_step: f32,
+    pub notch: bool,
+    pub wave: Waveform,
+    pub volume: f32,
+    pub help: bool
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            format: Format::Tui,
+            display: DisplayMode::Full,
+            art: true,
+            color: ColorChoice::Auto,
+            thresholds: Thresholds::default(),
+            reference: CONFIG.tuning,
+            preset: 0,
+            history: 10.0,
+            record: None,
+            append: false,
+            rotate: None,
+            midi_file: None,
+            midi_port: false,
+            midi_channel: 0,
+            bend_range: 2,
+            osc: None,
+            serve: None,
+            tone: None,
+            intonation: false,
+            piano: false,
+            stretch: 1.0,
+            drone: false,
+            drone_step: 4.0,
+            notch: false,
+            wave: Waveform::Sine,
+            volume: 0.5,
+            help: false
+        }
+    }
+}
+
+impl Args {
+
+    /// Parse the command line arguments, not including the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Args, Error> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => parsed.format = match value::<String, _>(&arg, &mut args)?.as_str() {
+                    "tui" => Format::Tui,
+                    "jsonl" => Format::Jsonl,
+                    other => return Err(Error::Parse(format!("invalid value '{}' for '--format'", other)))
+                },
+                "--compact" => parsed.display = DisplayMode::Compact,
+                "--no-art" => parsed.art = false,
+                "--color" => parsed.color = match value::<String, _>(&arg, &mut args)?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => return Err(Error::Parse(format!("invalid value '{}' for '--color'", other)))
+                },
+                "--in-tune" => parsed.thresholds.in_tune = value(&arg, &mut args)?,
+                "--close" => parsed.thresholds.close = value(&arg, &mut args)?,
+                "--a4" => parsed.reference = value(&arg, &mut args)?,
+                "--record" => parsed.record = Some(value(&arg, &mut args)?),
+                "--append" => parsed.append = true,
+                "--rotate" => parsed.rotate = Some(value(&arg, &mut args)?),
+                "--midi-file" => parsed.midi_file = Some(value(&arg, &mut args)?),
+                "--midi-port" => parsed.midi_port = true,
+                "--midi-channel" => {
+                    let channel: u8 = value(&arg, &mut args)?;
+                    if !(1..=16).contains(&channel) {
+                        return Err(Error::Parse(String::from("'--midi-channel' must be from 1 to 16")))
+                    }
+                    parsed.midi_channel = channel - 1;
+                }
+                "--bend-range" => parsed.bend_range = value(&arg, &mut args)?,
+                "--osc" => parsed.osc = Some(value(&arg, &mut args)?),
+                "--serve" => parsed.serve = Some(value(&arg, &mut args)?),
+                "--history" => parsed.history = value(&arg, &mut args)?,
+                "--preset" => {
+                    let name: String = value(&arg, &mut args)?;
+                    parsed.preset = Preset::find(&name).ok_or_else(|| {
+                        let names: Vec<_> = PRESETS.iter().map(|p| p.name).collect();
+                        Error::Parse(format!("unknown preset '{}', expected one of {}", name, names.join(", ")))
+                    })?;
+                }
+                "tone" => {
+                    let name: String = value(&arg, &mut args)?;
+                    parsed.tone = Some(parse_note(&name).ok_or_else(|| Error::Parse(format!("invalid note '{}', expected something like A4 or Bb2", name)))?);
+                }
+                "--intonation" => parsed.intonation = true,
+                "--piano" => parsed.piano = true,
+                "--stretch" => parsed.stretch = value(&arg, &mut args)?,
+                "--drone" => parsed.drone = true,
+                "--drone-step" => parsed.drone_step = value(&arg, &mut args)?,
+                "--notch" => parsed.notch = true,
+                "--wave" => parsed.wave = match value::<String, _>(&arg, &mut args)?.as_str() {
+                    "sine" => Waveform::Sine,
+                    "triangle" => Waveform::Triangle,
+                    "sawtooth" => Waveform::Sawtooth,
+                    "pluck" => Waveform::Pluck,
+                    other => return Err(Error::Parse(format!("invalid value '{}' for '--wave'", other)))
+                },
+                "--volume" => parsed.volume = value(&arg, &mut args)?,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(Error::Parse(format!("unrecognized argument '{}'", arg)))
+            }
+        }
+        if !(REFERENCE_RANGE.0..=REFERENCE_RANGE.1).contains(&parsed.reference) {
+            return Err(Error::Parse(format!("'--a4' must be from {} to {} Hz", REFERENCE_RANGE.0, REFERENCE_RANGE.1)))
+        }
+        if parsed.intonation && PRESETS[parsed.preset].strings.is_empty() {
+            return Err(Error::Parse(String::from("'--intonation' needs a '--preset' with strings to check")))
+        }
+        if parsed.piano && parsed.preset != 0 {
+            return Err(Error::Parse(String::from("'--piano' can't be used with a '--preset'")))
+        }
+        if parsed.stretch.is_nan() || parsed.stretch < 0.0 {
+            return Err(Error::Parse(String::from("'--stretch' can't be negative")))
+        }
+        if parsed.drone && PRESETS[parsed.preset].strings.is_empty() {
+            return Err(Error::Parse(String::from("'--drone' needs a '--preset' with strings to play")))
+        }
+        if parsed.notch && !parsed.drone {
+            return Err(Error::Parse(String::from("'--notch' needs '--drone'")))
+        }
+        if parsed.drone_step.is_nan() || parsed.drone_step < 0.0 {
+            return Err(Error::Parse(String::from("'--drone-step' can't be negative")))
+        }
+        if !(0.0..=1.0).contains(&parsed.volume) {
+            return Err(Error::Parse(String::from("'--volume' must be from 0 to 1")))
+        }
+        if parsed.rotate == Some(0) {
+            return Err(Error::Parse(String::from("'--rotate' needs at least one row per file")))
+        }
+        if parsed.record.is_none() && (parsed.append || parsed.rotate.is_some()) {
+            return Err(Error::Parse(String::from("'--append' and '--rotate' need '--record'")))
+        }
+        if parsed.bend_range > 24 {
+            return Err(Error::Parse(String::from("'--bend-range' can be at most 24 semitones")))
+        }
+        if parsed.history.is_nan() || parsed.history < 0.0 {
+            return Err(Error::Parse(String::from("'--history' can't be negative")))
+        }
+        if parsed.thresholds.in_tune > parsed.thresholds.close {
+            return Err(Error::Parse(String::from("'--in-tune' can't be larger than '--close'")))
+        }
+        Ok(parsed)
+    }
+}
+
+/// Take and parse the value following the option `name`.
+fn value<T: FromStr, I: Iterator<Item = String>>(name: &str, args: &mut I) -> Result<T, Error> {
+    let raw = args.next().ok_or_else(|| Error::Parse(format!("'{}' needs a value", name)))?;
+    raw.parse().map_err(|_| Error::Parse(format!("invalid value '{}' for '{}'", raw, name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use clituner::error::EXIT_USAGE;
+    use clituner::theme::{ColorChoice, Thresholds};
+    use clituner::tone::Waveform;
+    use super::{Args, DisplayMode, Format};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|a| a.to_string())).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+        assert_eq!(Args::default().display, DisplayMode::Full);
+        assert!(Args::default().art);
+    }
+
+    #[test]
+    fn test_parse_compact_no_art() {
+        let args = parse(&["--compact", "--no-art"]).unwrap();
+        assert_eq!(args.display, DisplayMode::Compact);
+        assert!(!args.art);
+    }
+
+    #[test]
+    fn test_parse_unknown_argument() {
+        assert!(parse(&["--bogus"]).is_err());
+        let err = Args::parse(vec![String::from("--bogus")]).unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_parse_color_and_thresholds() {
+        let args = parse(&["--color", "never", "--in-tune", "5", "--close", "20"]).unwrap();
+        assert_eq!(args.color, ColorChoice::Never);
+        assert_eq!(args.thresholds, Thresholds { in_tune: 5, close: 20 });
+        assert!(parse(&["--color", "sometimes"]).is_err());
+        assert!(parse(&["--in-tune"]).is_err());
+        assert!(parse(&["--in-tune", "40"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_reference_and_preset() {
+        let args = parse(&["--a4", "440", "--preset", "violin"]).unwrap();
+        assert_eq!(args.reference, 440.0);
+        assert_eq!(args.preset, 4);
+        assert!(parse(&["--preset", "banjo"]).is_err());
+        assert!(parse(&["--a4", "0"]).is_err());
+        assert!(parse(&["--a4", "inf"]).is_err());
+        assert!(parse(&["--a4", "NaN"]).is_err());
+        assert!(parse(&["--a4", "1e30"]).is_err());
+        assert_eq!(parse(&["--a4", "392"]).unwrap_err(), "'--a4' must be from 400 to 480 Hz");
+        assert_eq!(parse(&["--a4", "415"]).unwrap().reference, 415.0);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse(&["--format", "jsonl"]).unwrap().format, Format::Jsonl);
+        assert_eq!(parse(&[]).unwrap().format, Format::Tui);
+        assert!(parse(&["--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let args = parse(&["--record", "out.csv", "--append", "--rotate", "100"]).unwrap();
+        assert_eq!(args.record, Some("out.csv".into()));
+        assert!(args.append);
+        assert_eq!(args.rotate, Some(100));
+        assert!(parse(&["--append"]).is_err());
+        assert!(parse(&["--record", "out.csv", "--rotate", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_outputs() {
+        let args = parse(&["--midi-file", "out.mid", "--midi-channel", "10", "--osc", "localhost:57120", "--serve", "127.0.0.1:8080"]).unwrap();
+        assert_eq!(args.midi_file, Some("out.mid".into()));
+        assert_eq!(args.midi_channel, 9);
+        assert_eq!(args.osc.as_deref(), Some("localhost:57120"));
+        assert_eq!(args.serve.as_deref(), Some("127.0.0.1:8080"));
+        assert!(parse(&["--midi-channel", "0"]).is_err());
+        assert!(parse(&["--bend-range", "48"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_tone() {
+        let args = parse(&["tone", "Bb2", "--wave", "pluck", "--volume", "0.2"]).unwrap();
+        assert_eq!(args.tone, Some(46));
+        assert_eq!(args.wave, Waveform::Pluck);
+        assert_eq!(args.volume, 0.2);
+        assert_eq!(parse(&[]).unwrap().tone, None);
+        assert!(parse(&["tone", "X4"]).is_err());
+        assert!(parse(&["tone"]).is_err());
+        assert!(parse(&["--volume", "2"]).is_err());
+        assert!(parse(&["--wave", "square"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_drone() {
+        let args = parse(&["--preset", "cello", "--drone", "--drone-step", "2.5", "--notch"]).unwrap();
+        assert!(args.drone && args.notch);
+        assert_eq!(args.drone_step, 2.5);
+        assert!(parse(&["--drone"]).is_err());
+        assert!(parse(&["--notch"]).is_err());
+        assert!(parse(&["--preset", "cello", "--drone", "--drone-step", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_piano() {
+        let args = parse(&["--piano", "--stretch", "0.5"]).unwrap();
+        assert!(args.piano);
+        assert_eq!(args.stretch, 0.5);
+        assert!(parse(&["--piano", "--preset", "guitar"]).is_err());
+        assert!(parse(&["--stretch", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_intonation() {
+        assert!(parse(&["--preset", "guitar", "--intonation"]).unwrap().intonation);
+        assert!(parse(&["--intonation"]).is_err());
+    }
+}
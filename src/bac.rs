@@ -1,3 +1,8 @@
+pub mod bitstream;
 pub mod conf;
 pub mod decl;
 pub mod imp;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod wide;
@@ -0,0 +1,287 @@
+use crate::bac::conf::{Window, CONFIG};
+#[cfg(feature = "std")]
+use crate::bac::imp::Imperative;
+
+/// Counts the bits that differ between the first half of a bitstream and the
+/// bitstream shifted by each position, the heart of the autocorrelation. A
+/// kernel can keep working space from one call to the next.
+pub trait Kernel: Default {
+    /// Fill `corr[start_pos..]` with the counts for those shifts, comparing
+    /// `mid_array` words.
+    fn correlate(&mut self, bits: &[u32], mid_array: usize, start_pos: usize, corr: &mut [u32]);
+}
+
+/// The zero crossings of a signal as bits, sized by its `Window`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Bitstream<K = Imperative> {
+    window: Window,
+    bits: Vec<u32>,
+    corr: Vec<u32>,
+    kernel: K
+}
+
+#[derive(Clone, Debug)]
+pub struct ZeroCross {
+    y: bool
+}
+
+impl ZeroCross {
+    pub fn new() -> Self {
+        ZeroCross { y: false }
+    }
+
+    pub fn run(&mut self, s: f32) -> bool {
+        if s < -0.1 {
+            self.y = false
+        }
+        if s > 0.0 {
+            self.y = true
+        }
+        self.y
+    }
+}
+
+impl Default for ZeroCross {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Kernel> Default for Bitstream<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Kernel> Bitstream<K> {
+
+    pub fn new() -> Self {
+        Self::with_window(Window::default())
+    }
+
+    pub fn with_window(window: Window) -> Self {
+        Bitstream { window, bits: vec![0; window.array_size], corr: vec![0; window.mid_pos], kernel: K::default() }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|x| *x = 0)
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        get(&self.bits, i)
+    }
+
+    pub fn set(&mut self, i: usize, val: bool) {
+        set(&mut self.bits, i, val)
+    }
+
+    /// Correlate the bitstream from `start_pos` into its own buffer, returning the
+    /// highest count and its index. The counts stay readable through `correlation`.
+    pub fn autocorrelate(&mut self, start_pos: usize) -> (u32, usize) {
+        self.corr.iter_mut().for_each(|x| *x = 0);
+        self.kernel.correlate(&self.bits, self.window.mid_array, start_pos, &mut self.corr);
+        strongest(&self.corr, start_pos)
+    }
+
+    /// The correlation from the last `autocorrelate` or `estimate`.
+    pub fn correlation(&self) -> &[u32] {
+        &self.corr
+    }
+
+    pub fn handle_harmonics(&self, max_count: u32, est_index: usize, corr: &[u32]) -> usize {
+        handle_harmonics(&self.window, max_count, est_index, corr)
+    }
+
+    pub fn estimate_pitch(signal: &[f32]) -> Option<f32> {
+        Self::estimate_pitch_with_confidence(signal).map(|(freq, _)| freq)
+    }
+
+    /// Like `estimate_pitch`, also returning how periodic the signal is from 0 to 1,
+    /// based on how well the bitstream matches itself shifted by the best period.
+    pub fn estimate_pitch_with_confidence(signal: &[f32]) -> Option<(f32, f32)> {
+        Self::new().estimate(signal)
+    }
+
+    /// The pitch and confidence of the first `buff_size` samples of `signal` within
+    /// this bitstream's window, reusing its buffers rather than allocating.
+    pub fn estimate(&mut self, signal: &[f32]) -> Option<(f32, f32)> {
+        estimate_into(&mut self.kernel, &self.window, signal, &mut self.bits, &mut self.corr)
+    }
+}
+
+/// The pitch and confidence of the first `buff_size` samples of `signal` within
+/// `window`, without allocating. `bits` needs `array_size` words and `corr`
+/// `mid_pos` counts, e.g. `[u32; 64]` and `[u32; 1024]` for the default window.
+pub fn estimate_with<K: Kernel>(window: &Window, signal: &[f32], bits: &mut [u32], corr: &mut [u32]) -> Option<(f32, f32)> {
+    estimate_into(&mut K::default(), window, signal, bits, corr)
+}
+
+fn estimate_into<K: Kernel>(kernel: &mut K, window: &Window, signal: &[f32], bits: &mut [u32], corr: &mut [u32]) -> Option<(f32, f32)> {
+    if bits.len() < window.array_size || corr.len() < window.mid_pos {
+        return None
+    }
+    let (bits, corr) = (&mut bits[..window.array_size], &mut corr[..window.mid_pos]);
+    bits.iter_mut().for_each(|x| *x = 0);
+    let mut zc = ZeroCross::new();
+    for (i, s) in signal.iter().take(window.buff_size).enumerate() {
+        set(bits, i, zc.run(*s));
+    }
+    kernel.correlate(bits, window.mid_array, first_shift(window), corr);
+    estimate_from(window, signal, corr)
+}
+
+/// The first shift correlated, one short of `min_period` so `handle_harmonics`
+/// can see both sides of a period just over it.
+pub(crate) fn first_shift(window: &Window) -> usize {
+    window.min_period.saturating_sub(1)
+}
+
+pub fn get(bits: &[u32], i: usize) -> bool {
+    let mask = 1 << (i % CONFIG.nbits);
+    (bits[i / CONFIG.nbits] & mask) != 0
+}
+
+pub fn set(bits: &mut [u32], i: usize, val: bool) {
+    // Gets the section of 32 bits
+    // where i resides
+    let bs = &mut bits[i / CONFIG.nbits];
+
+    // Creates a bitmask the 1 is at
+    // the location of interest in the 32 bits
+    let mask = 1 << (i % CONFIG.nbits);
+
+    // will be either all zeros or all ones.
+    // All zeros is identity element with XOR
+    let id= if val { u32::MAX } else { 0 };
+    *bs ^= (id ^ *bs) & mask;
+}
+
+/// The pitch and confidence of `signal` given the correlation of its bitstream
+/// from `first_shift` on.
+pub(crate) fn estimate_from(window: &Window, signal: &[f32], corr: &[u32]) -> Option<(f32, f32)> {
+    let (count, est_index) = strongest(corr, window.min_period);
+    let confidence = if count == 0 { 0.0 } else { 1.0 - corr[est_index] as f32 / count as f32 };
+    let est_index = handle_harmonics(window, count, est_index, corr);
+    estimate_pitch_with_index(window, signal, est_index).map(|freq| (freq, confidence))
+}
+
+/// How much worse than the best correlation, as a fraction of the highest count,
+/// a shorter period can match and still be taken as the real one. A dominant
+/// 2nd or 3rd harmonic matches at a fraction of the period too, but only to
+/// within about 6% where the period itself is within 2%.
+const SUB_TOLERANCE: f32 = 0.03;
+
+/// Correct `est_index` when it's a multiple of the period rather than the period.
+///
+/// The bitstream matches itself at every multiple of its period, and a period
+/// that isn't a whole number of samples matches best at the multiple that comes
+/// closest to one, so the best correlation can be several periods long. For each
+/// number of periods `div` that `est_index` could span, largest first, the
+/// correlation has to be nearly as good as at `est_index` at every
+/// `k * est_index / div` for `k` in `1..div`. The first `div` for which it is
+/// gives the period. Only correlations from just below `min_period` on are looked
+/// at, so an `est_index` outside `corr` is left as it is.
+pub fn handle_harmonics(window: &Window, max_count: u32, est_index: usize, corr: &[u32]) -> usize {
+    if est_index >= corr.len() || window.min_period == 0 {
+        return est_index
+    }
+    let threshold = corr[est_index] as f32 + SUB_TOLERANCE * max_count as f32;
+    let max_div = est_index / window.min_period;
+    for div in (2..=max_div).rev() {
+        let all_strong = (1..div).all(|k| {
+            let sub_period = (k * est_index) as f32 / div as f32;
+            dip(corr, sub_period, window.min_period).is_some_and(|c| c <= threshold)
+        });
+        if all_strong {
+            return est_index / div
+        }
+    }
+    est_index
+}
+
+/// The correlation at a period `x` that falls between samples. Either side of a
+/// period the count rises in a straight line, so carry on the lines through the
+/// two samples below `x` and the two above it. Both have to reach down for the
+/// dip to be there.
+fn dip(corr: &[u32], x: f32, min_period: usize) -> Option<f32> {
+    let below = x as usize;
+    let above = below + 1;
+    if below < min_period || above + 1 >= corr.len() {
+        return None
+    }
+    let c = |i: usize| corr[i] as f32;
+    let left = c(below) - (c(below - 1) - c(below)) * (x - below as f32);
+    let right = c(above) - (c(above + 1) - c(above)) * (above as f32 - x);
+    Some(left.max(right).max(0.0))
+}
+
+/// Refine the period `est_index` to a fraction of a sample and return its frequency.
+///
+/// Starting from the first rising zero crossing, look for the rising crossing
+/// nearest to where each following period should end, keeping the average of
+/// the periods found so far as the length of the next one. Periods with no
+/// crossing near enough, e.g. in noise, are skipped, and there's no pitch
+/// without at least one.
+fn estimate_pitch_with_index(window: &Window, signal: &[f32], est_index: usize) -> Option<f32> {
+    let signal = &signal[..signal.len().min(window.buff_size)];
+    if est_index == 0 || est_index >= signal.len() {
+        return None
+    }
+    let first = (1..est_index).find_map(|i| rising_edge(signal, i))?;
+    let tolerance = est_index as f32 / 4.0;
+    let mut period = est_index as f32;
+    let (mut periods, mut n) = (0, 1);
+    loop {
+        let expected = first + n as f32 * period;
+        if expected + tolerance >= signal.len() as f32 {
+            break
+        }
+        let from = (expected - tolerance).max(1.0) as usize;
+        let to = (expected + tolerance) as usize + 1;
+        let nearest = (from..to.min(signal.len()))
+            .filter_map(|i| rising_edge(signal, i))
+            .min_by(|a, b| (a - expected).abs().total_cmp(&(b - expected).abs()));
+        if let Some(edge) = nearest {
+            periods = n;
+            period = (edge - first) / n as f32;
+        }
+        n += 1;
+    }
+    if periods == 0 || period <= 0.0 {
+        return None
+    }
+    Some(window.samples_per_second as f32 / period)
+}
+
+/// Where the signal crosses zero going up between samples `i - 1` and `i`,
+/// found by drawing a line between them.
+fn rising_edge(signal: &[f32], i: usize) -> Option<f32> {
+    let (prev, next) = (signal[i - 1], signal[i]);
+    if !(prev <= 0.0 && next > 0.0) {
+        return None
+    }
+    let fraction = -prev / (next - prev);
+    if fraction.is_finite() { Some((i - 1) as f32 + fraction.min(1.0)) } else { None }
+}
+
+/// The highest count and the position of the lowest, the best period.
+fn strongest(corr: &[u32], start_pos: usize) -> (u32, usize) {
+    let mut max_count = 0;
+    let mut min_count = u32::MAX;
+    let mut est_index = 0;
+    for (pos, &count) in corr.iter().enumerate().skip(start_pos) {
+        max_count = max_count.max(count);
+        if count < min_count {
+            min_count = count;
+            est_index = pos;
+        }
+    }
+    (max_count, est_index)
+}
@@ -6,12 +6,14 @@ const MIN_PERIOD: usize = SAMPLES_PER_SECOND / MAX_FREQ;
 const MAX_PERIOD: usize = SAMPLES_PER_SECOND / MIN_FREQ;
 const BUFF_SIZE: usize = get_smallest_pow2(MAX_PERIOD) * 2;
 const ARRAY_SIZE: usize = BUFF_SIZE / NBITS;
+#[allow(clippy::unnecessary_cast)]
 const MID_ARRAY: usize = ((ARRAY_SIZE / 2) - 1) as usize ;
+#[allow(clippy::unnecessary_cast)]
 const MID_POS: usize = (BUFF_SIZE / 2) as usize;
 
 pub struct Config {
-    pub(crate) amp_threshold: f32,
-    pub(crate) tuning: f32,
+    pub amp_threshold: f32,
+    pub tuning: f32,
     pub(crate) nbits: usize,
     pub samples_per_second: usize,
     pub min_period: usize,
@@ -33,6 +35,63 @@ pub const CONFIG: Config = Config{
     mid_pos: MID_POS
 };
 
+/// The sizes a `Bitstream` works with, worked out from the range of frequencies
+/// it should detect. `CONFIG`'s are the default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Window {
+    pub samples_per_second: usize,
+    pub min_period: usize,
+    pub buff_size: usize,
+    /// Words in the bitstream.
+    pub array_size: usize,
+    /// Words compared for each shift.
+    pub mid_array: usize,
+    /// Shifts tried, half the buffer.
+    pub mid_pos: usize
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            samples_per_second: CONFIG.samples_per_second,
+            min_period: CONFIG.min_period,
+            buff_size: CONFIG.buff_size,
+            array_size: CONFIG.array_size,
+            mid_array: CONFIG.mid_array,
+            mid_pos: CONFIG.mid_pos
+        }
+    }
+}
+
+impl Window {
+
+    /// A window for frequencies from `min_freq` to `max_freq` Hz, or None if the
+    /// range is empty or above half the sample rate.
+    pub fn new(samples_per_second: usize, min_freq: usize, max_freq: usize) -> Option<Self> {
+        if min_freq == 0 || min_freq >= max_freq || 2 * max_freq > samples_per_second {
+            return None
+        }
+        let max_period = samples_per_second / min_freq;
+        // Short enough periods would leave the autocorrelation less than a word to slide over.
+        let buff_size = (get_smallest_pow2(max_period) * 2).max(4 * NBITS);
+        let array_size = buff_size / NBITS;
+        Some(Window {
+            samples_per_second,
+            min_period: samples_per_second / max_freq,
+            buff_size,
+            array_size,
+            mid_array: (array_size / 2) - 1,
+            mid_pos: buff_size / 2
+        })
+    }
+
+    /// The default range of frequencies at another sample rate, or None if the
+    /// rate is too low for it.
+    pub fn with_rate(samples_per_second: usize) -> Option<Self> {
+        Window::new(samples_per_second, MIN_FREQ, MAX_FREQ)
+    }
+}
+
 /// Calculate the smallest power of 2 greater than n.
 /// Useful for getting the appropriate buffer size
 pub const fn get_smallest_pow2(n: usize) -> usize {
@@ -0,0 +1,195 @@
+use crate::bac::bitstream::{estimate_from, first_shift, set, Kernel, ZeroCross};
+use crate::bac::conf::{Window, CONFIG};
+use crate::bac::imp::Imperative;
+
+/// A bitstream over the most recent `buff_size` samples of a stream. Pushing a
+/// hop of samples shifts their zero crossings in and updates the correlation by
+/// what enters and leaves it, rather than rebuilding it for every window.
+#[derive(Clone, Debug)]
+pub struct StreamingBitstream<K = Imperative> {
+    window: Window,
+    kernel: K,
+    zc: ZeroCross,
+    /// A ring of the window's samples, each written twice, `buff_size` apart, so
+    /// the window is always the `buff_size` from `head`.
+    samples: Vec<f32>,
+    head: usize,
+    filled: usize,
+    /// The window's bits, with room for a window's worth past its end.
+    bits: Vec<u32>,
+    corr: Vec<u32>
+}
+
+impl<K: Kernel> Default for StreamingBitstream<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Kernel> StreamingBitstream<K> {
+
+    pub fn new() -> Self {
+        Self::with_window(Window::default())
+    }
+
+    pub fn with_window(window: Window) -> Self {
+        StreamingBitstream {
+            window,
+            kernel: K::default(),
+            zc: ZeroCross::new(),
+            samples: vec![0.0; 2 * window.buff_size],
+            head: 0,
+            filled: 0,
+            bits: vec![0; 2 * window.array_size + 2],
+            corr: vec![0; window.mid_pos]
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// The samples in the window, once it's full.
+    pub fn samples(&self) -> &[f32] {
+        let size = self.window.buff_size;
+        if self.filled < size {
+            &self.samples[..self.filled]
+        } else {
+            &self.samples[self.head..self.head + size]
+        }
+    }
+
+    /// The correlation of the window's bitstream, as `Bitstream::autocorrelate`
+    /// would give it.
+    pub fn correlation(&self) -> &[u32] {
+        &self.corr
+    }
+
+    /// Add samples, returning the pitch and confidence of the latest window once
+    /// there are enough to fill it.
+    pub fn push(&mut self, mut samples: &[f32]) -> Option<(f32, f32)> {
+        let size = self.window.buff_size;
+        if samples.len() >= size {
+            // Nothing of the old window is left, start again with the end of this one.
+            let skip = samples.len() - size;
+            samples[..skip].iter().for_each(|&s| { self.zc.run(s); });
+            samples = &samples[skip..];
+            self.filled = 0;
+            self.head = 0;
+            self.bits.iter_mut().for_each(|x| *x = 0);
+        }
+
+        if self.filled < size {
+            let take = samples.len().min(size - self.filled);
+            for &s in &samples[..take] {
+                set(&mut self.bits, self.filled, self.zc.run(s));
+                self.record(s);
+                self.filled += 1;
+            }
+            if self.filled < size {
+                return None
+            }
+            self.correlate();
+            samples = &samples[take..];
+        }
+
+        if !samples.is_empty() {
+            self.advance(samples);
+        }
+        estimate_from(&self.window, self.samples(), &self.corr)
+    }
+
+    fn record(&mut self, s: f32) {
+        let size = self.window.buff_size;
+        self.samples[self.head] = s;
+        self.samples[self.head + size] = s;
+        self.head = (self.head + 1) % size;
+    }
+
+    fn correlate(&mut self) {
+        let window = self.window;
+        self.kernel.correlate(&self.bits[..window.array_size], window.mid_array, first_shift(&window), &mut self.corr);
+    }
+
+    /// Slide the full window along by `hop`, which is shorter than the window.
+    fn advance(&mut self, hop: &[f32]) {
+        let size = self.window.buff_size;
+        for (i, &s) in hop.iter().enumerate() {
+            set(&mut self.bits, size + i, self.zc.run(s));
+            self.record(s);
+        }
+        let (window, n) = (self.window, hop.len());
+        // Updating compares `n` bits twice for each shift where rebuilding compares
+        // the `mid_array` words, so past half of those it's cheaper to rebuild.
+        let rebuild = n > window.mid_array * CONFIG.nbits / 2;
+        if !rebuild {
+            update(&self.bits, &mut self.corr, first_shift(&window), window.mid_array, n);
+        }
+        for i in 0..window.array_size {
+            self.bits[i] = word_at(&self.bits, n + i * CONFIG.nbits);
+        }
+        self.bits[window.array_size..].iter_mut().for_each(|x| *x = 0);
+        if rebuild {
+            self.correlate();
+        }
+    }
+}
+
+/// Take the `n` bits that stop being compared out of the correlation and add
+/// the `n` after the compared ones that start.
+#[cfg(target_arch = "x86_64")]
+fn update(bits: &[u32], corr: &mut [u32], first: usize, mid_array: usize, n: usize) {
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("popcnt") {
+        unsafe { update_avx2(bits, corr, first, mid_array, n) }
+    } else {
+        update_words(bits, corr, first, mid_array, n)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn update(bits: &[u32], corr: &mut [u32], first: usize, mid_array: usize, n: usize) {
+    update_words(bits, corr, first, mid_array, n)
+}
+
+/// The same loop, vectorised over the shifts.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,popcnt")]
+unsafe fn update_avx2(bits: &[u32], corr: &mut [u32], first: usize, mid_array: usize, n: usize) {
+    update_words(bits, corr, first, mid_array, n)
+}
+
+#[inline(always)]
+fn update_words(bits: &[u32], corr: &mut [u32], first: usize, mid_array: usize, n: usize) {
+    let words = n.div_ceil(CONFIG.nbits);
+    let rest = n % CONFIG.nbits;
+    let last = if rest == 0 { u32::MAX } else { (1 << rest) - 1 };
+    // A word of the `n` bits at a time against each shift. The shifts in a
+    // chunk of 32 compare with bits from the same two words.
+    for w in 0..words {
+        let mask = if w + 1 == words { last } else { u32::MAX };
+        let (added, removed) = (bits[mid_array + w], bits[w]);
+        for (k, chunk) in corr.chunks_mut(CONFIG.nbits).enumerate() {
+            if (k + 1) * CONFIG.nbits <= first {
+                continue
+            }
+            let (after, before) = (pair(bits, mid_array + w + k), pair(bits, w + k));
+            for (shift, c) in chunk.iter_mut().enumerate() {
+                if k * CONFIG.nbits + shift >= first {
+                    *c += ((added ^ (after >> shift) as u32) & mask).count_ones();
+                    *c -= ((removed ^ (before >> shift) as u32) & mask).count_ones();
+                }
+            }
+        }
+    }
+}
+
+/// Words `i` and `i + 1` as one, the first in the low half.
+#[inline(always)]
+fn pair(bits: &[u32], i: usize) -> u64 {
+    bits[i] as u64 | (bits[i + 1] as u64) << 32
+}
+
+/// The 32 bits starting at bit `i`.
+fn word_at(bits: &[u32], i: usize) -> u32 {
+    (pair(bits, i / CONFIG.nbits) >> (i % CONFIG.nbits)) as u32
+}
@@ -0,0 +1,120 @@
+use crate::bac::bitstream::Kernel;
+
+/// Compares 64 bits at a time, packing pairs of words together.
+#[derive(Clone, Debug, Default)]
+pub struct Wide {
+    /// The packed words, kept so they're only allocated once.
+    words: Vec<u64>
+}
+
+/// Like `Wide`, counting with AVX2 or the popcnt instruction when the CPU has them.
+#[derive(Clone, Debug, Default)]
+pub struct Simd {
+    words: Vec<u64>
+}
+
+/// Counts the differing bits between the first `n` words and the words from
+/// `index` shifted down by `shift` bits.
+type Count = fn(&[u64], usize, u32, usize) -> u32;
+
+impl Kernel for Wide {
+    fn correlate(&mut self, bits: &[u32], mid_array: usize, start_pos: usize, corr: &mut [u32]) {
+        pack(bits, &mut self.words);
+        correlate(&self.words, mid_array, start_pos, corr, |words, index, shift, n| count(words, index, shift, 0, n))
+    }
+}
+
+impl Kernel for Simd {
+    fn correlate(&mut self, bits: &[u32], mid_array: usize, start_pos: usize, corr: &mut [u32]) {
+        pack(bits, &mut self.words);
+        correlate(&self.words, mid_array, start_pos, corr, select())
+    }
+}
+
+/// Pairs of words as one into `words`, the first in the low half so bit `i`
+/// stays bit `i`.
+fn pack(bits: &[u32], words: &mut Vec<u64>) {
+    words.clear();
+    words.extend(bits.chunks_exact(2).map(|pair| pair[0] as u64 | (pair[1] as u64) << 32));
+}
+
+fn correlate(words: &[u64], mid_array: usize, start_pos: usize, corr: &mut [u32], count: Count) {
+    // `mid_array` counts 32 bit words, an odd one out is compared on its own.
+    let full = mid_array / 2;
+    for (pos, c) in corr.iter_mut().enumerate().skip(start_pos) {
+        let (index, shift) = (pos / 64, (pos % 64) as u32);
+        *c = count(words, index, shift, full);
+        if mid_array % 2 == 1 {
+            *c += ((words[full] ^ shifted(words, index + full, shift)) as u32).count_ones();
+        }
+    }
+}
+
+#[inline(always)]
+fn shifted(words: &[u64], i: usize, shift: u32) -> u64 {
+    if shift == 0 { words[i] } else { (words[i] >> shift) | (words[i + 1] << (64 - shift)) }
+}
+
+#[inline(always)]
+fn count(words: &[u64], index: usize, shift: u32, from: usize, n: usize) -> u32 {
+    (from..n).map(|i| (words[i] ^ shifted(words, index + i, shift)).count_ones()).sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn select() -> Count {
+    if is_x86_feature_detected!("avx2") {
+        |words, index, shift, n| unsafe { x86::count_avx2(words, index, shift, n) }
+    } else if is_x86_feature_detected!("popcnt") {
+        |words, index, shift, n| unsafe { x86::count_popcnt(words, index, shift, n) }
+    } else {
+        |words, index, shift, n| count(words, index, shift, 0, n)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn select() -> Count {
+    |words, index, shift, n| count(words, index, shift, 0, n)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+    use super::count;
+
+    /// The scalar loop, compiled so `count_ones` is a single instruction.
+    #[target_feature(enable = "popcnt")]
+    pub unsafe fn count_popcnt(words: &[u64], index: usize, shift: u32, n: usize) -> u32 {
+        count(words, index, shift, 0, n)
+    }
+
+    /// Four words at a time, counting the bits of each byte by looking up its
+    /// nibbles and summing the bytes of each word with `sad`.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count_avx2(words: &[u64], index: usize, shift: u32, n: usize) -> u32 {
+        let lookup = _mm256_setr_epi8(
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4
+        );
+        let nibble = _mm256_set1_epi8(0x0f);
+        let right = _mm_cvtsi32_si128(shift as i32);
+        // Shifting by 64 clears the word, which is what a shift of 0 needs.
+        let left = _mm_cvtsi32_si128(64 - shift as i32);
+        let mut total = _mm256_setzero_si256();
+        let mut i = 0;
+        while i + 4 <= n && index + i + 5 <= words.len() {
+            let a = _mm256_loadu_si256(words.as_ptr().add(i) as *const __m256i);
+            let b = _mm256_loadu_si256(words.as_ptr().add(index + i) as *const __m256i);
+            let c = _mm256_loadu_si256(words.as_ptr().add(index + i + 1) as *const __m256i);
+            let x = _mm256_xor_si256(a, _mm256_or_si256(_mm256_srl_epi64(b, right), _mm256_sll_epi64(c, left)));
+            let bytes = _mm256_add_epi8(
+                _mm256_shuffle_epi8(lookup, _mm256_and_si256(x, nibble)),
+                _mm256_shuffle_epi8(lookup, _mm256_and_si256(_mm256_srli_epi16(x, 4), nibble))
+            );
+            total = _mm256_add_epi64(total, _mm256_sad_epu8(bytes, _mm256_setzero_si256()));
+            i += 4;
+        }
+        let mut lanes = [0u64; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, total);
+        lanes.iter().sum::<u64>() as u32 + count(words, index, shift, i, n)
+    }
+}
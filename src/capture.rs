@@ -0,0 +1,134 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use clituner::drone::Drone;
+use clituner::error::Error;
+use clituner::reading::{unix_time, Reading};
+use clituner::tone::Tone;
+use clituner::tuner::Tuner;
+use crate::args::Args;
+
+/// What went wrong on a stream's own thread, kept for the main thread.
+#[derive(Debug, Default)]
+struct Errors {
+    /// The error that stopped the stream.
+    fatal: Option<Error>,
+    /// Errors the stream carried on after, like buffer overruns.
+    recovered: usize
+}
+
+/// A running stream, stopped when dropped.
+pub struct Stream {
+    _stream: cpal::Stream,
+    errors: Arc<Mutex<Errors>>
+}
+
+impl Stream {
+
+    /// Stop the stream, returning the error that stopped it early if there was
+    /// one and otherwise how many errors it recovered from.
+    pub fn finish(self) -> Result<usize, Error> {
+        let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+        match errors.fatal.take() {
+            Some(err) => Err(err),
+            None => Ok(errors.recovered)
+        }
+    }
+}
+
+/// Note an error from the stream, returning true if the stream is done for.
+fn record_error(errors: &Mutex<Errors>, err: cpal::StreamError) -> bool {
+    let mut errors = errors.lock().unwrap_or_else(|e| e.into_inner());
+    match err {
+        cpal::StreamError::DeviceNotAvailable => {
+            errors.fatal.get_or_insert(Error::Stream(err));
+            true
+        }
+        cpal::StreamError::BackendSpecific { .. } => {
+            errors.recovered += 1;
+            false
+        }
+    }
+}
+
+/// Start listening on the default input device. `tuner` is given the sample
+/// rate and returns the tuner the samples go through, each reading being sent
+/// on to `sender`. If the device goes away the sender is dropped, so whoever
+/// is receiving knows to stop.
+pub fn open_input<T: FnOnce(u32) -> Result<Tuner, Error>>(tuner: T, sender: Sender<Reading>) -> Result<Stream, Error> {
+    // lowest frequency determines buf_size. We need twice the period worth of samples
+    // https://www.cycfi.com/2018/04/fast-and-efficient-pitch-detection-bliss/
+
+    let host = cpal::default_host();
+    let device = host.default_input_device().ok_or(Error::NoDevice("input"))?;
+    let config = device.default_input_config()?.config();
+
+    let mut tuner = tuner(config.sample_rate.0)?;
+    let sender = Arc::new(Mutex::new(Some(sender)));
+    let stopped = Arc::clone(&sender);
+    let errors = Arc::new(Mutex::new(Errors::default()));
+    let stream_errors = Arc::clone(&errors);
+    let stream = device.build_input_stream(
+        &config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let readings = tuner.push_samples(data);
+            if let Some(sender) = sender.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                for reading in readings {
+                    // The receiver only goes away when we're shutting down.
+                    let _ = sender.send(reading);
+                }
+            }
+        },
+        move |err| {
+            if record_error(&stream_errors, err) {
+                stopped.lock().unwrap_or_else(|e| e.into_inner()).take();
+            }
+        },
+    )?;
+    stream.play()?;
+    Ok(Stream { _stream: stream, errors })
+}
+
+/// Start playing on the default output device. `source` is given the sample
+/// rate and number of channels and returns what fills each buffer.
+pub fn open_output<S, F>(source: S) -> Result<Stream, Error>
+where
+    S: FnOnce(u32, usize) -> F,
+    F: FnMut(&mut [f32]) + Send + 'static
+{
+    let host = cpal::default_host();
+    let device = host.default_output_device().ok_or(Error::NoDevice("output"))?;
+    let config = device.default_output_config()?.config();
+
+    let mut fill = source(config.sample_rate.0, config.channels as usize);
+    let errors = Arc::new(Mutex::new(Errors::default()));
+    let stream_errors = Arc::clone(&errors);
+    let stream = device.build_output_stream(
+        &config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| fill(data),
+        move |err| {
+            record_error(&stream_errors, err);
+        },
+    )?;
+    stream.play()?;
+    Ok(Stream { _stream: stream, errors })
+}
+
+/// Play the drone, moving on to the next string when it's time.
+pub fn open_drone(drone: Arc<Mutex<Drone>>, args: &Args) -> Result<Stream, Error> {
+    open_output(|sample_rate, channels| {
+        let frequency = drone.lock().unwrap_or_else(|e| e.into_inner()).frequency().unwrap_or(args.reference);
+        let mut tone = Tone::new(args.wave, frequency, sample_rate, args.volume);
+        move |data: &mut [f32]| {
+            match drone.lock().unwrap_or_else(|e| e.into_inner()).update(unix_time()) {
+                Some(frequency) => {
+                    if frequency != tone.frequency() {
+                        tone.set_frequency(frequency);
+                    }
+                    tone.fill(data, channels);
+                }
+                None => data.iter_mut().for_each(|s| *s = 0.0)
+            }
+        }
+    })
+}
@@ -0,0 +1,476 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+use colored::{Color, ColoredString, Colorize};
+use crossterm::event::{self, Event};
+use clituner::error::Error;
+use clituner::history::History;
+use clituner::intonation::{Intonation, Prompt, INTONATED_CENTS};
+use clituner::note::{midi_to_freq, note_name, pitch_class};
+use clituner::reading::Reading;
+use clituner::sink::Tap;
+use clituner::stability::{Analysis, Stability};
+use clituner::theme::{Band, Palette, Theme};
+use crate::args::{Args, DisplayMode};
+use crate::state::{Action, State, KEY_HELP};
+use crate::tui::{terminal_error, Screen, Style, Terminal};
+
+const GREETING: &str = r#"
+ *******  **           **     **    **   ********** **      ** ********   ********** **      ** ** ****     **   ********
+/**////**/**          ****   //**  **   /////**/// /**     /**/**/////   /////**/// /**     /**/**/**/**   /**  **//////**
+/**   /**/**         **//**   //****        /**    /**     /**/**            /**    /**     /**/**/**//**  /** **      //
+/******* /**        **  //**   //**         /**    /**********/*******       /**    /**********/**/** //** /**/**
+/**////  /**       **********   /**         /**    /**//////**/**////        /**    /**//////**/**/**  //**/**/**    *****
+/**      /**      /**//////**   /**         /**    /**     /**/**            /**    /**     /**/**/**   //****//**  ////**
+/**      /********/**     /**   /**         /**    /**     /**/********      /**    /**     /**/**/**    //*** //********
+//       //////// //      //    //          //     //      // ////////       //     //      // // //      ///   ////////
+"#;
+
+const PLAIN_GREETING: &str = "PLAY THE THING";
+
+const C: &str = r#"
+   ******
+  **////**
+ **    //
+/**
+/**
+//**    **
+ //******
+  //////
+"#;
+
+const C_SHARP: &str = r#"
+   ******
+  **////**   **    **
+ **    //  ************
+/**       ///**////**/
+/**         /**   /**
+//**    ** ************
+ //****** ///**////**/
+  //////    //    //
+"#;
+
+const D: &str = r#"
+ *******
+/**////**
+/**    /**
+/**    /**
+/**    /**
+/**    **
+/*******
+///////
+"#;
+
+const D_SHARP: &str = r#"
+ *******
+/**////**    **    **
+/**    /** ************
+/**    /**///**////**/
+/**    /**  /**   /**
+/**    **  ************
+/*******  ///**////**/
+///////     //    //
+"#;
+
+const E: &str = r#"
+ ********
+/**/////
+/**
+/*******
+/**////
+/**
+/********
+////////
+"#;
+
+const F: &str = r#"
+ ********
+/**/////
+/**
+/*******
+/**////
+/**
+/**
+//
+"#;
+
+const F_SHARP: &str = r#"
+ ********
+/**/////    **    **
+/**       ************
+/******* ///**////**/
+/**////    /**   /**
+/**       ************
+/**      ///**////**/
+//         //    //
+"#;
+
+const G: &str = r#"
+   ********
+  **//////**
+ **      //
+/**
+/**    *****
+//**  ////**
+ //********
+  ////////
+"#;
+
+const G_SHARP: &str = r#"
+   ********
+  **//////**   **    **
+ **      //  ************
+/**         ///**////**/
+/**    *****  /**   /**
+//**  ////** ************
+ //******** ///**////**/
+  ////////    //    //
+"#;
+
+const A: &str = r#"
+     **
+    ****
+   **//**
+  **  //**
+ **********
+/**//////**
+/**     /**
+//      //
+"#;
+
+const A_SHARP: &str = r#"
+     **
+    ****      **    **
+   **//**   ************
+  **  //** ///**////**/
+ **********  /**   /**
+/**//////** ************
+/**     /**///**////**/
+//      //   //    //
+"#;
+
+const B: &str = r#"
+ ******
+/*////**
+/*   /**
+/******
+/*//// **
+/*    /**
+/*******
+///////
+"#;
+
+const FLAT: &str = r#"
+     **
+   **/ **
+ **   // **
+//      //
+"#;
+
+const SHARP: &str = r#"
+/**   /**
+//** /**
+ //****
+  //**
+"#;
+
+const NOTES: [&str; 12] = [C, C_SHARP, D, D_SHARP, E, F, F_SHARP, G, G_SHARP, A, A_SHARP, B];
+
+/// Number of 10 cent steps on either side of the compact meter.
+const METER_STEPS: i32 = 5;
+
+/// Height of the flat sign, the tallest glyph and the sharp sign stacked.
+const MESSAGE_HEIGHT: usize = 19;
+
+/// Rows taken by the status line under the note and the gap above it.
+const STATUS_HEIGHT: usize = 2;
+
+/// Rows taken by the held note analysis, the settings and key help at the bottom.
+const FOOTER_HEIGHT: usize = 3;
+
+/// The history graph is only shown with at least this many rows to spare, and never taller than the max.
+const GRAPH_HEIGHT: (usize, usize) = (4, 12);
+
+/// How often the history graph scrolls when there are no new readings.
+const GRAPH_REFRESH: Duration = Duration::from_millis(100);
+
+fn block_width(block: &str) -> usize {
+    block.lines().map(|l| l.chars().count()).max().unwrap_or(0)
+}
+
+fn draw_greeting<W: Write>(screen: &mut Screen<W>, art: bool, height: usize, theme: &Theme) {
+    let greeting = if art { GREETING } else { PLAIN_GREETING };
+    let x = screen.width().saturating_sub(block_width(greeting)) / 2;
+    let y = height.saturating_sub(greeting.lines().count()) / 2;
+    screen.put_block(x, y, greeting, accent(theme.palette));
+}
+
+/// Draw the note centred in the top `height` rows of the screen.
+fn draw_message<W: Write>(screen: &mut Screen<W>, freq: f32, height: usize, state: &State, theme: &Theme) {
+    let (midi_note, cents) = state.target(freq);
+    let note = NOTES[pitch_class(midi_note)];
+    let style = cents_style(theme, cents);
+    let width = block_width(note).max(block_width(FLAT));
+    let x = screen.width().saturating_sub(width) / 2;
+    let y = height.saturating_sub(MESSAGE_HEIGHT + STATUS_HEIGHT) / 2;
+
+    // The flat sign sits above the note and the sharp sign below it,
+    // the note itself stays in place either way.
+    if cents < 0 {
+        screen.put_block(x, y, FLAT, style);
+    }
+    screen.put_block(x, y + 5, note, style);
+    if cents >= 0 {
+        screen.put_block(x, y + 14, SHARP, style);
+    }
+
+    let status = format!("{}  {}  {:.2} Hz  {:+} cents", theme.indicator(cents), note_name(midi_note), freq, cents);
+    let status_x = screen.width().saturating_sub(status.chars().count()) / 2;
+    screen.put_str(status_x, y + MESSAGE_HEIGHT + 1, &status, style);
+}
+
+/// Rows available for the history graph on a screen `height` rows tall.
+fn graph_height(height: usize, history: &History) -> usize {
+    let spare = height.saturating_sub(MESSAGE_HEIGHT + STATUS_HEIGHT + FOOTER_HEIGHT + 1);
+    if history.span() > 0.0 && spare >= GRAPH_HEIGHT.0 { spare.min(GRAPH_HEIGHT.1) } else { 0 }
+}
+
+fn draw_graph<W: Write>(screen: &mut Screen<W>, freq: f32, history: &History, now: f32, top: usize, height: usize, state: &State) {
+    let (midi_note, _) = state.target(freq);
+    let target = midi_to_freq(midi_note, state.reference);
+    let lines = history.render(now, target, screen.width(), height);
+    for (i, line) in lines.iter().enumerate() {
+        screen.put_str(0, top + i, line, Default::default());
+    }
+}
+
+fn draw_footer<W: Write>(screen: &mut Screen<W>, state: &State, analysis: Option<Analysis>) {
+    let bottom = screen.height().saturating_sub(1);
+    if let Some(analysis) = analysis {
+        screen.put_str(0, bottom.saturating_sub(2), &analysis.summary(), Default::default());
+    }
+    screen.put_str(0, bottom.saturating_sub(1), &state.status(), Default::default());
+    screen.put_str(0, bottom, KEY_HELP, Default::default());
+}
+
+/// A meter with a slot per 10 cents either side of the centre line,
+/// filled outwards in the direction the note is off.
+fn cents_meter(cents: i32) -> String {
+    let filled = ((cents.abs() + 9) / 10).min(METER_STEPS);
+    let slot = |lit: bool| if lit { '▮' } else { '▯' };
+    let flat: String = (0..METER_STEPS).rev().map(|i| slot(cents < 0 && i < filled)).collect();
+    let sharp: String = (0..METER_STEPS).map(|i| slot(cents > 0 && i < filled)).collect();
+    format!("{}|{}", flat, sharp)
+}
+
+fn compact_line(freq: f32, state: &State, theme: &Theme) -> String {
+    let (midi_note, cents) = state.target(freq);
+    let style = cents_style(theme, cents);
+    let note = format!("{:<4}", note_name(midi_note));
+    format!(
+        "{} {:>4}¢  {:>7.2} Hz  {} {}{}",
+        paint(theme.palette, &note, style),
+        cents,
+        freq,
+        paint(theme.palette, &cents_meter(cents), style),
+        theme.indicator(cents),
+        if state.frozen { "  frozen" } else { "" }
+    )
+}
+
+/// Keyboard driven loop, switching between the full screen and compact displays.
+pub fn run_interactive(mut terminal: Terminal, receiver: Receiver<Reading>, tap: &Tap, args: &Args, mut state: State, theme: &Theme) -> Result<(), Error> {
+    let (width, height) = Terminal::size().map_err(terminal_error)?;
+    let mut screen = Screen::new(io::stdout(), width, height);
+    let mut stdout = io::stdout();
+    let mut last_freq = None;
+    let mut history = History::new(args.history);
+    let mut stability = Stability::new();
+    // Readings are plotted at their timestamps from the first one's, and the
+    // graph scrolls on from the latest as time passes.
+    let mut first_timestamp = None;
+    let mut latest = (0.0, Instant::now());
+    let mut last_draw = Instant::now();
+    let mut dirty = true;
+    loop {
+        if event::poll(Duration::from_millis(20)).map_err(terminal_error)? {
+            match event::read().map_err(terminal_error)? {
+                Event::Key(key) => match state.handle_key(key) {
+                    Action::Quit => break,
+                    Action::Redraw => dirty = true,
+                    Action::Nothing => {}
+                },
+                Event::Resize(width, height) => {
+                    screen.resize(width, height);
+                    dirty = true;
+                }
+                _ => {}
+            }
+        }
+        let drained = loop {
+            match receiver.try_recv() {
+                Ok(reading) => {
+                    tap.send(state.frame(reading));
+                    if !state.frozen {
+                        let time = (reading.timestamp - *first_timestamp.get_or_insert(reading.timestamp)) as f32;
+                        history.push(time, reading.frequency);
+                        latest = (time, Instant::now());
+                        stability.push(reading);
+                        last_freq = Some(reading.frequency);
+                        dirty = true;
+                    }
+                }
+                Err(err) => break err
+            }
+        };
+        // The input has stopped for good, main will say why.
+        if drained == TryRecvError::Disconnected {
+            break;
+        }
+        let scrolling = !state.frozen && !history.is_empty() && state.display == DisplayMode::Full;
+        if scrolling && last_draw.elapsed() >= GRAPH_REFRESH {
+            dirty = true;
+        }
+        if !dirty {
+            continue;
+        }
+        dirty = false;
+        last_draw = Instant::now();
+
+        let full = state.display == DisplayMode::Full;
+        if full != terminal.is_alternate() {
+            terminal.set_alternate(full).map_err(terminal_error)?;
+            screen.invalidate();
+        }
+        if full {
+            screen.clear();
+            let graph_rows = graph_height(screen.height(), &history);
+            let bottom = screen.height().saturating_sub(FOOTER_HEIGHT);
+            let message_rows = bottom.saturating_sub(graph_rows);
+            match last_freq {
+                Some(freq) => {
+                    draw_message(&mut screen, freq, message_rows, &state, theme);
+                    if graph_rows > 0 {
+                        let now = latest.0 + latest.1.elapsed().as_secs_f32();
+                        draw_graph(&mut screen, freq, &history, now, message_rows, graph_rows, &state);
+                    }
+                }
+                None => draw_greeting(&mut screen, args.art, message_rows, theme)
+            }
+            draw_footer(&mut screen, &state, stability.analysis());
+            screen.present().map_err(|err| Error::io("could not draw on the terminal", err))?;
+        } else {
+            let line = match last_freq {
+                Some(freq) => compact_line(freq, &state, theme),
+                None => paint(theme.palette, PLAIN_GREETING, accent(theme.palette)).to_string()
+            };
+            // Return to the start of the line and clear whatever is left of the last one.
+            print!("\r{}\x1B[K", line);
+            stdout.flush().map_err(|err| Error::io("could not draw on the terminal", err))?;
+        }
+    }
+    if !terminal.is_alternate() {
+        print!("\r\n");
+    }
+    Ok(())
+}
+
+/// Compact output without keyboard controls, for when stdin isn't a terminal.
+pub fn run_compact(receiver: Receiver<Reading>, tap: &Tap, state: State, theme: &Theme) -> Result<(), Error> {
+    let stdout_error = |err| Error::io("could not write to stdout", err);
+    let mut stdout = io::stdout();
+    print!("{}", paint(theme.palette, PLAIN_GREETING, accent(theme.palette)));
+    stdout.flush().map_err(stdout_error)?;
+    for reading in receiver {
+        tap.send(state.frame(reading));
+        print!("\r{}\x1B[K", compact_line(reading.frequency, &state, theme));
+        stdout.flush().map_err(stdout_error)?;
+    }
+    Ok(())
+}
+
+/// Guide the player through comparing each string of the preset open and at the 12th fret.
+pub fn run_intonation(receiver: Receiver<Reading>, tap: &Tap, state: State, theme: &Theme) {
+    let mut intonation = Intonation::new(state.preset().strings, state.reference);
+    let show = |prompt| match prompt {
+        Prompt::Open(note) => println!("Play the open {} string and let it ring", note_name(note)),
+        Prompt::Fretted(note) => println!("Now play the {} string at the 12th fret", note_name(note)),
+        Prompt::Done => {}
+    };
+    show(intonation.prompt());
+    for reading in receiver {
+        tap.send(state.frame(reading));
+        let before = intonation.prompt();
+        if let Some(check) = intonation.push(reading) {
+            println!("  {}", paint(theme.palette, &check.summary(), intonation_style(check.cents(), theme)));
+        }
+        match intonation.prompt() {
+            Prompt::Done => break,
+            prompt if prompt != before => show(prompt),
+            _ => {}
+        }
+    }
+
+    if !intonation.checks().is_empty() {
+        println!();
+        for check in intonation.checks() {
+            println!("{}", paint(theme.palette, &check.summary(), intonation_style(check.cents(), theme)));
+        }
+    }
+}
+
+/// Intonation is held to a tighter standard than tuning so has its own bands.
+fn intonation_style(cents: i32, theme: &Theme) -> Style {
+    band_style(theme.palette, match cents.abs() {
+        c if c <= INTONATED_CENTS => Band::InTune,
+        c if c as u32 <= theme.thresholds.in_tune => Band::Close,
+        _ => Band::Far
+    })
+}
+
+fn band_style(palette: Palette, band: Band) -> Style {
+    match palette {
+        Palette::Color => Style::color(match band {
+            Band::InTune => Color::Green,
+            Band::Close => Color::Yellow,
+            Band::Far => Color::Red
+        }),
+        Palette::Mono => match band {
+            Band::InTune => Style { bold: true, inverse: true, ..Style::default() },
+            Band::Close => Style { bold: true, ..Style::default() },
+            Band::Far => Style::default()
+        },
+        Palette::Plain => Style::default()
+    }
+}
+
+fn cents_style(theme: &Theme, cents: i32) -> Style {
+    band_style(theme.palette, theme.band(cents))
+}
+
+/// Style used for the greeting and other text that isn't tied to a reading.
+fn accent(palette: Palette) -> Style {
+    match palette {
+        Palette::Color => Style::color(Color::Red),
+        Palette::Mono => Style { bold: true, ..Style::default() },
+        Palette::Plain => Style::default()
+    }
+}
+
+/// Apply a style to text printed outside of a `Screen`.
+fn paint(palette: Palette, text: &str, style: Style) -> ColoredString {
+    if palette == Palette::Plain {
+        return text.normal()
+    }
+    let mut s = match style.color {
+        Some(c) => text.color(c),
+        None => text.normal()
+    };
+    if style.bold {
+        s = s.bold();
+    }
+    if style.inverse {
+        s = s.reversed();
+    }
+    s
+}
@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+use crate::note::midi_to_freq;
+use crate::preset::Preset;
+
+/// Harmonics of the drone notched out of the input, starting with the fundamental.
+const NOTCHED_HARMONICS: usize = 3;
+
+/// Quality factor of each notch, higher is narrower.
+const NOTCH_Q: f32 = 30.0;
+
+/// Plays each string of a preset in turn, to tune against by ear.
+#[derive(Clone, Debug)]
+pub struct Drone {
+    strings: &'static [i32],
+    reference: f32,
+    /// Seconds on each string before moving on, 0 to stay on one.
+    step: f64,
+    index: usize,
+    held: bool,
+    /// When the current string started, unset until the next update.
+    since: Option<f64>
+}
+
+impl Drone {
+
+    pub fn new(preset: &'static Preset, reference: f32, step: f64) -> Self {
+        Drone { strings: preset.strings, reference, step, index: 0, held: false, since: None }
+    }
+
+    /// Start again from the first string of another preset.
+    pub fn set_preset(&mut self, preset: &'static Preset) {
+        self.strings = preset.strings;
+        self.index = 0;
+        self.since = None;
+    }
+
+    pub fn set_reference(&mut self, reference: f32) {
+        self.reference = reference;
+    }
+
+    /// The MIDI note being played, if the preset has any strings.
+    pub fn note(&self) -> Option<i32> {
+        self.strings.get(self.index).copied()
+    }
+
+    pub fn frequency(&self) -> Option<f32> {
+        self.note().map(|note| midi_to_freq(note, self.reference))
+    }
+
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    /// Stay on the current string, or carry on cycling.
+    pub fn toggle_hold(&mut self) {
+        self.held = !self.held;
+        self.since = None;
+    }
+
+    pub fn next(&mut self) {
+        if !self.strings.is_empty() {
+            self.index = (self.index + 1) % self.strings.len();
+        }
+        self.since = None;
+    }
+
+    /// Move on to the next string once the current one has had its time, and
+    /// return the frequency to play.
+    pub fn update(&mut self, now: f64) -> Option<f32> {
+        match self.since {
+            Some(since) if !self.held && self.step > 0.0 && now - since >= self.step => {
+                self.next();
+                self.since = Some(now);
+            }
+            Some(_) => {}
+            None => self.since = Some(now)
+        }
+        self.frequency()
+    }
+}
+
+/// A biquad notch filter.
+#[derive(Clone, Debug)]
+pub struct Notch {
+    sample_rate: f32,
+    frequency: Option<f32>,
+    /// Feedforward then feedback coefficients, normalised so a0 is 1.
+    b: [f32; 3],
+    a: [f32; 2],
+    x: [f32; 2],
+    y: [f32; 2]
+}
+
+impl Notch {
+
+    /// A filter letting everything through until it's given a frequency.
+    pub fn new(sample_rate: u32) -> Self {
+        Notch { sample_rate: sample_rate as f32, frequency: None, b: [1.0, 0.0, 0.0], a: [0.0, 0.0], x: [0.0; 2], y: [0.0; 2] }
+    }
+
+    pub fn set_frequency(&mut self, frequency: Option<f32>) {
+        if frequency == self.frequency {
+            return
+        }
+        self.frequency = frequency;
+        match frequency {
+            Some(f) if f > 0.0 && f < self.sample_rate / 2.0 => {
+                let w0 = 2.0 * PI * f / self.sample_rate;
+                let alpha = w0.sin() / (2.0 * NOTCH_Q);
+                let a0 = 1.0 + alpha;
+                let cos = -2.0 * w0.cos() / a0;
+                self.b = [1.0 / a0, cos, 1.0 / a0];
+                self.a = [cos, (1.0 - alpha) / a0];
+            }
+            _ => {
+                self.b = [1.0, 0.0, 0.0];
+                self.a = [0.0, 0.0];
+            }
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// Takes the drone and its first few harmonics out of the input, so the
+/// tuner hears the instrument rather than the speaker.
+#[derive(Clone, Debug)]
+pub struct DroneFilter {
+    notches: Vec<Notch>
+}
+
+impl DroneFilter {
+
+    pub fn new(sample_rate: u32) -> Self {
+        DroneFilter { notches: vec![Notch::new(sample_rate); NOTCHED_HARMONICS] }
+    }
+
+    pub fn set_frequency(&mut self, frequency: Option<f32>) {
+        for (harmonic, notch) in self.notches.iter_mut().enumerate() {
+            notch.set_frequency(frequency.map(|f| f * (harmonic + 1) as f32));
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        self.notches.iter_mut().fold(x, |x, notch| notch.process(x))
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can stop the tuner. Some variants only exist with the
+/// features that can cause them, so matching needs a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No audio device to listen or play on, the direction being "input" or "output".
+    NoDevice(&'static str),
+    /// The device's sample rate, in Hz, is too low for the range of pitches detected.
+    SampleRate(u32),
+    /// The device has no configuration we can use.
+    #[cfg(feature = "capture")]
+    UnsupportedConfig(cpal::DefaultStreamConfigError),
+    #[cfg(feature = "capture")]
+    BuildStream(cpal::BuildStreamError),
+    #[cfg(feature = "capture")]
+    PlayStream(cpal::PlayStreamError),
+    /// The stream failed while running, e.g. the device was unplugged.
+    #[cfg(feature = "capture")]
+    Stream(cpal::StreamError),
+    /// Reading or writing a file.
+    File { path: PathBuf, source: io::Error },
+    /// Any other I/O, the terminal or the network, with what was being done.
+    Io { context: String, source: io::Error },
+    /// Bad command line arguments.
+    Parse(String)
+}
+
+/// Exit code for bad arguments, as most command line tools use.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Exit code when there's something wrong with the audio devices.
+pub const EXIT_AUDIO: i32 = 3;
+
+impl Error {
+
+    pub fn file<P: Into<PathBuf>>(path: P, source: io::Error) -> Self {
+        Error::File { path: path.into(), source }
+    }
+
+    pub fn io<S: Into<String>>(context: S, source: io::Error) -> Self {
+        Error::Io { context: context.into(), source }
+    }
+
+    /// The code the process should exit with.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Parse(_) => EXIT_USAGE,
+            Error::NoDevice(_) | Error::SampleRate(_) => EXIT_AUDIO,
+            #[cfg(feature = "capture")]
+            Error::UnsupportedConfig(_)
+            | Error::BuildStream(_)
+            | Error::PlayStream(_)
+            | Error::Stream(_) => EXIT_AUDIO,
+            Error::File { .. } | Error::Io { .. } => 1
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoDevice(direction) => write!(f, "no audio {} device found, is one plugged in and enabled?", direction),
+            Error::SampleRate(rate) => write!(f, "the audio device's sample rate of {} Hz is too low to tune with", rate),
+            #[cfg(feature = "capture")]
+            Error::UnsupportedConfig(err) => write!(f, "the audio device has no usable configuration: {}", err),
+            #[cfg(feature = "capture")]
+            Error::BuildStream(err) => write!(f, "could not open the audio stream: {}", err),
+            #[cfg(feature = "capture")]
+            Error::PlayStream(err) => write!(f, "could not start the audio stream: {}", err),
+            #[cfg(feature = "capture")]
+            Error::Stream(err) => write!(f, "the audio stream stopped: {}", err),
+            Error::File { path, source } => write!(f, "could not open {}: {}", path.display(), source),
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+            Error::Parse(msg) => f.write_str(msg)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "capture")]
+            Error::UnsupportedConfig(err) => Some(err),
+            #[cfg(feature = "capture")]
+            Error::BuildStream(err) => Some(err),
+            #[cfg(feature = "capture")]
+            Error::PlayStream(err) => Some(err),
+            #[cfg(feature = "capture")]
+            Error::Stream(err) => Some(err),
+            Error::File { source, .. } | Error::Io { source, .. } => Some(source),
+            Error::NoDevice(_) | Error::SampleRate(_) | Error::Parse(_) => None
+        }
+    }
+}
+
+#[cfg(feature = "capture")]
+impl From<cpal::DefaultStreamConfigError> for Error {
+    fn from(err: cpal::DefaultStreamConfigError) -> Self {
+        Error::UnsupportedConfig(err)
+    }
+}
+
+#[cfg(feature = "capture")]
+impl From<cpal::BuildStreamError> for Error {
+    fn from(err: cpal::BuildStreamError) -> Self {
+        Error::BuildStream(err)
+    }
+}
+
+#[cfg(feature = "capture")]
+impl From<cpal::PlayStreamError> for Error {
+    fn from(err: cpal::PlayStreamError) -> Self {
+        Error::PlayStream(err)
+    }
+}
+
+#[cfg(feature = "capture")]
+impl From<cpal::StreamError> for Error {
+    fn from(err: cpal::StreamError) -> Self {
+        Error::Stream(err)
+    }
+}
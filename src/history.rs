@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use crate::note::cents_off;
+
+/// Largest deviation in cents plotted, anything further off is drawn at the edge.
+pub const RANGE: f32 = 50.0;
+
+/// Width of the axis labels to the left of the plot.
+pub const LABEL_WIDTH: usize = 4;
+
+/// Recent frequency readings, used to plot how the pitch moves over time.
+#[derive(Clone, Debug)]
+pub struct History {
+    /// Seconds of history kept.
+    span: f32,
+    /// Time in seconds and frequency of each reading, oldest first.
+    readings: VecDeque<(f32, f32)>
+}
+
+impl History {
+
+    pub fn new(span: f32) -> Self {
+        History { span, readings: VecDeque::new() }
+    }
+
+    pub fn span(&self) -> f32 {
+        self.span
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    /// Add a reading taken at `time` seconds, dropping those that scrolled out.
+    pub fn push(&mut self, time: f32, freq: f32) {
+        self.readings.push_back((time, freq));
+        while let Some(&(t, _)) = self.readings.front() {
+            if t >= time - self.span {
+                break;
+            }
+            self.readings.pop_front();
+        }
+    }
+
+    /// Plot the deviation from `target` in cents as braille dots, with the
+    /// newest readings at the right edge. Each character cell holds two
+    /// columns of four dots, and the middle of the plot is in tune.
+    pub fn render(&self, now: f32, target: f32, width: usize, height: usize) -> Vec<String> {
+        let plot_width = width.saturating_sub(LABEL_WIDTH);
+        let mut dots = vec![0u8; plot_width * height];
+        let (dot_cols, dot_rows) = (plot_width * 2, height * 4);
+        if dot_cols > 0 && dot_rows > 0 {
+            for &(time, freq) in self.readings.iter() {
+                let age = now - time;
+                if age < 0.0 || age > self.span {
+                    continue;
+                }
+                let col = ((1.0 - age / self.span) * (dot_cols - 1) as f32).round() as usize;
+                let cents = (cents_off(freq, target) as f32).clamp(-RANGE, RANGE);
+                let row = ((RANGE - cents) / (2.0 * RANGE) * (dot_rows - 1) as f32).round() as usize;
+                dots[(row / 4) * plot_width + col / 2] |= braille_bit(col % 2, row % 4);
+            }
+        }
+
+        (0..height).map(|y| {
+            let label = if y == 0 {
+                format!("+{}", RANGE)
+            } else if y == height / 2 {
+                String::from("0")
+            } else if y == height - 1 {
+                format!("-{}", RANGE)
+            } else {
+                String::new()
+            };
+            let plot: String = dots[y * plot_width..(y + 1) * plot_width].iter()
+                .map(|&d| match d {
+                    0 => ' ',
+                    _ => std::char::from_u32(0x2800 + d as u32).unwrap_or(' ')
+                })
+                .collect();
+            format!("{:>w$}{}", label, plot, w = LABEL_WIDTH.min(width))
+        }).collect()
+    }
+}
+
+/// The bit for the dot at `col` (0 or 1) and `row` (0 to 3, top to bottom)
+/// within a braille character.
+fn braille_bit(col: usize, row: usize) -> u8 {
+    const BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+    BITS[col][row]
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use crate::note::{cents_off, midi_to_freq, note_name};
+use crate::reading::Reading;
+
+/// Seconds a note has to be held steady before it's captured.
+const STABLE_TIME: f64 = 0.5;
+
+/// Furthest any reading can wander from the others and still count as steady.
+const STABLE_CENTS: f32 = 5.0;
+
+/// How far out of tune a string can be and still be recognised.
+const RECOGNISE_CENTS: i32 = 100;
+
+/// Deviation of the 12th fret small enough to leave the saddle alone.
+pub const INTONATED_CENTS: i32 = 2;
+
+/// What the player should do next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prompt {
+    /// Play the string with this MIDI note open.
+    Open(i32),
+    /// Play the same string at the 12th fret.
+    Fretted(i32),
+    Done
+}
+
+/// The result for one string.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Check {
+    pub note: i32,
+    pub open: f32,
+    pub fretted: f32
+}
+
+impl Check {
+
+    /// Cents the 12th fret is sharp of an octave above the open string.
+    pub fn cents(&self) -> i32 {
+        cents_off(self.fretted, 2.0 * self.open)
+    }
+
+    pub fn advice(&self) -> &'static str {
+        match self.cents() {
+            c if c > INTONATED_CENTS => "sharp, move the saddle back",
+            c if c < -INTONATED_CENTS => "flat, move the saddle forward",
+            _ => "in tune, leave the saddle"
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} 12th fret {:+}¢ {}", note_name(self.note), self.cents(), self.advice())
+    }
+}
+
+/// Walks through the strings of a preset comparing each open string to its 12th fret.
+#[derive(Clone, Debug)]
+pub struct Intonation {
+    strings: &'static [i32],
+    reference: f32,
+    string: usize,
+    /// The open string once it's been captured.
+    open: Option<f32>,
+    /// Recent readings close to the note being waited for.
+    recent: VecDeque<Reading>,
+    checks: Vec<Check>
+}
+
+impl Intonation {
+
+    pub fn new(strings: &'static [i32], reference: f32) -> Self {
+        Intonation { strings, reference, string: 0, open: None, recent: VecDeque::new(), checks: Vec::new() }
+    }
+
+    pub fn prompt(&self) -> Prompt {
+        match (self.strings.get(self.string), self.open) {
+            (None, _) => Prompt::Done,
+            (Some(&note), None) => Prompt::Open(note),
+            (Some(&note), Some(_)) => Prompt::Fretted(note)
+        }
+    }
+
+    pub fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+
+    /// Take a reading, returning the string's check once both notes are captured.
+    pub fn push(&mut self, reading: Reading) -> Option<Check> {
+        let expected = match self.prompt() {
+            Prompt::Open(note) => midi_to_freq(note, self.reference),
+            Prompt::Fretted(_) => 2.0 * self.open?,
+            Prompt::Done => return None
+        };
+        if cents_off(reading.frequency, expected).abs() > RECOGNISE_CENTS {
+            self.recent.clear();
+            return None
+        }
+        self.recent.push_back(reading);
+        while self.recent.front().is_some_and(|r| reading.timestamp - r.timestamp > STABLE_TIME) {
+            self.recent.pop_front();
+        }
+
+        let captured = self.steady()?;
+        self.recent.clear();
+        match self.open {
+            None => {
+                self.open = Some(captured);
+                None
+            }
+            Some(open) => {
+                let check = Check { note: self.strings[self.string], open, fretted: captured };
+                self.checks.push(check);
+                self.string += 1;
+                self.open = None;
+                Some(check)
+            }
+        }
+    }
+
+    /// The mean frequency once the recent readings span long enough and agree.
+    fn steady(&self) -> Option<f32> {
+        let (first, last) = (self.recent.front()?, self.recent.back()?);
+        if last.timestamp - first.timestamp < STABLE_TIME * 0.8 {
+            return None
+        }
+        let mean = self.recent.iter().map(|r| r.frequency).sum::<f32>() / self.recent.len() as f32;
+        let spread = self.recent.iter()
+            .map(|r| (1200.0 * (r.frequency / mean).log2()).abs())
+            .fold(0.0, f32::max);
+        if spread <= STABLE_CENTS { Some(mean) } else { None }
+    }
+}
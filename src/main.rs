@@ -1,5 +1,214 @@
-use clituner;
+mod args;
+mod capture;
+mod display;
+mod state;
+mod tui;
+
+use std::env;
+use std::process;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use clituner::bac::conf::Window;
+use clituner::error::Error;
+#[cfg(any(feature = "smf", feature = "midi-port"))]
+use clituner::midi::MidiSink;
+use clituner::note::{midi_to_freq, note_name};
+use clituner::osc::OscSender;
+use clituner::reading::Reading;
+#[cfg(feature = "csv")]
+use clituner::record::CsvRecorder;
+use clituner::sink::{Sink, Tap};
+use clituner::stability::Stability;
+use clituner::theme::{Palette, Theme};
+use clituner::tone::Tone;
+use clituner::tuner::Tuner;
+use clituner::web::WebServer;
+use crate::args::{Args, DisplayMode, Format, USAGE};
+use crate::capture::{open_drone, open_input, open_output};
+use crate::display::{run_compact, run_interactive, run_intonation};
+use crate::state::State;
+use crate::tui::{terminal_error, Terminal};
 
 fn main() {
-    clituner::main()
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprint!("{}\n\n{}", err, USAGE);
+            process::exit(err.exit_code());
+        }
+    };
+    if args.help {
+        print!("{}", USAGE);
+        return;
+    }
+    if let Err(err) = run(args) {
+        eprintln!("clituner: {}", err);
+        process::exit(err.exit_code());
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    if let Some(note) = args.tone {
+        return run_tone(note, &args)
+    }
+    let state = State::new(&args);
+    let tap = Tap::new(open_sinks(&args)?);
+
+    let (sender, receiver) = mpsc::channel();
+    let input = open_input(|sample_rate| {
+        let window = Window::with_rate(sample_rate as usize).ok_or(Error::SampleRate(sample_rate))?;
+        let mut tuner = Tuner::new().with_window(window).with_gate(state.gate());
+        if let Some(stretch) = state.stretch() {
+            tuner = tuner.with_stretch(stretch);
+        }
+        Ok(match state.drone() {
+            Some(drone) if args.notch => tuner.with_notch(drone, sample_rate),
+            _ => tuner
+        })
+    }, sender)?;
+    let drone = state.drone().map(|drone| open_drone(drone, &args)).transpose()?;
+
+    let result = if args.format == Format::Jsonl {
+        run_jsonl(receiver, &tap, state);
+        Ok(())
+    } else {
+        let palette = Palette::detect(
+            args.color,
+            env::var("NO_COLOR").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+            io::stdout().is_terminal()
+        );
+        // `colored` has its own idea of when to colour, make it agree with ours.
+        colored::control::set_override(palette != Palette::Plain);
+        let theme = Theme { palette, thresholds: args.thresholds };
+
+        if args.intonation {
+            run_intonation(receiver, &tap, state, &theme);
+            Ok(())
+        } else {
+            match Terminal::enter() {
+                Ok(terminal) => run_interactive(terminal, receiver, &tap, &args, state, &theme),
+                Err(_) if args.display == DisplayMode::Compact => run_compact(receiver, &tap, state, &theme),
+                Err(err) => Err(terminal_error(err))
+            }
+        }
+    };
+    report_errors(tap);
+
+    let mut recovered = input.finish()?;
+    if let Some(drone) = drone {
+        recovered += drone.finish()?;
+    }
+    if recovered > 0 {
+        eprintln!("clituner: carried on after {} audio stream errors", recovered);
+    }
+    result
+}
+
+/// Play `note` on the default output device until Enter is pressed.
+fn run_tone(note: i32, args: &Args) -> Result<(), Error> {
+    let frequency = midi_to_freq(note, args.reference);
+    let stream = open_output(|sample_rate, channels| {
+        let mut tone = Tone::new(args.wave, frequency, sample_rate, args.volume);
+        move |data: &mut [f32]| tone.fill(data, channels)
+    })?;
+
+    println!("Playing {} at {:.2} Hz, press Enter to stop", note_name(note), frequency);
+    let _ = io::stdin().read_line(&mut String::new());
+    stream.finish().map(|_| ())
+}
+
+/// One JSON object per reading, for scripts and test rigs.
+fn run_jsonl(receiver: Receiver<Reading>, tap: &Tap, state: State) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut stability = Stability::new();
+    for reading in receiver {
+        let frame = state.frame(reading);
+        tap.send(frame);
+        stability.push(reading);
+        let mut line = reading.to_json(frame.midi_note, frame.cents);
+        if let Some(analysis) = stability.analysis() {
+            // Into the reading's object rather than a line of its own.
+            line.insert_str(line.len() - 1, &format!(",\"stability\":{}", analysis.to_json()));
+        }
+        // Stop quietly when whoever is reading the output goes away.
+        if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+}
+
+/// Everything readings should be sent to besides the display.
+fn open_sinks(args: &Args) -> Result<Vec<Box<dyn Sink>>, Error> {
+    let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+    if let Some(path) = &args.record {
+        sinks.push(open_recorder(path, args)?);
+    }
+    if let Some(path) = &args.midi_file {
+        sinks.push(open_midi_file(path, args)?);
+    }
+    if args.midi_port {
+        sinks.push(open_midi_port(args)?);
+    }
+    if let Some(target) = &args.osc {
+        let sender = OscSender::new(target.as_str())
+            .map_err(|err| Error::io(format!("could not send OSC to {}", target), err))?;
+        sinks.push(Box::new(sender));
+    }
+    if let Some(addr) = &args.serve {
+        let server = WebServer::bind(addr.as_str(), args.thresholds)
+            .map_err(|err| Error::io(format!("could not serve on {}", addr), err))?;
+        sinks.push(Box::new(server));
+    }
+    Ok(sinks)
+}
+
+#[cfg(feature = "csv")]
+fn open_recorder(path: &Path, args: &Args) -> Result<Box<dyn Sink>, Error> {
+    let recorder = CsvRecorder::create(path, args.append, args.rotate)
+        .map_err(|err| Error::file(path, err))?;
+    Ok(Box::new(recorder))
+}
+
+#[cfg(not(feature = "csv"))]
+fn open_recorder(_path: &Path, _args: &Args) -> Result<Box<dyn Sink>, Error> {
+    Err(Error::Parse(String::from("'--record' needs clituner built with the 'csv' feature")))
+}
+
+#[cfg(feature = "smf")]
+fn open_midi_file(path: &Path, args: &Args) -> Result<Box<dyn Sink>, Error> {
+    let smf = clituner::midi::SmfWriter::create(path, args.midi_channel)
+        .map_err(|err| Error::file(path, err))?;
+    Ok(Box::new(MidiSink::new(smf, args.bend_range)))
+}
+
+#[cfg(not(feature = "smf"))]
+fn open_midi_file(_path: &Path, _args: &Args) -> Result<Box<dyn Sink>, Error> {
+    Err(Error::Parse(String::from("'--midi-file' needs clituner built with the 'smf' feature")))
+}
+
+#[cfg(all(target_os = "linux", feature = "midi-port"))]
+fn open_midi_port(args: &Args) -> Result<Box<dyn Sink>, Error> {
+    let port = clituner::midi::AlsaPort::open(args.midi_channel)
+        .map_err(|err| Error::io("could not create a MIDI port", err))?;
+    Ok(Box::new(MidiSink::new(port, args.bend_range)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_midi_port(_args: &Args) -> Result<Box<dyn Sink>, Error> {
+    Err(Error::Parse(String::from("'--midi-port' is only supported on Linux")))
+}
+
+#[cfg(all(target_os = "linux", not(feature = "midi-port")))]
+fn open_midi_port(_args: &Args) -> Result<Box<dyn Sink>, Error> {
+    Err(Error::Parse(String::from("'--midi-port' needs clituner built with the 'midi-port' feature")))
+}
+
+/// Errors from the sinks can only be shown once the display is gone.
+fn report_errors(tap: Tap) {
+    for err in tap.finish() {
+        eprintln!("error writing readings: {}", err);
+    }
 }
@@ -0,0 +1,32 @@
+//! The float functions the core needs, from `std` when it's there and `libm`
+//! when it isn't.
+
+#[cfg(feature = "std")]
+pub fn powf(x: f32, y: f32) -> f32 {
+    x.powf(y)
+}
+
+#[cfg(not(feature = "std"))]
+pub fn powf(x: f32, y: f32) -> f32 {
+    libm::powf(x, y)
+}
+
+#[cfg(feature = "std")]
+pub fn log2(x: f32) -> f32 {
+    x.log2()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn log2(x: f32) -> f32 {
+    libm::log2f(x)
+}
+
+#[cfg(feature = "std")]
+pub fn round(x: f32) -> f32 {
+    x.round()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn round(x: f32) -> f32 {
+    libm::roundf(x)
+}
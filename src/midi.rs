@@ -0,0 +1,325 @@
+#[cfg(feature = "smf")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "smf")]
+use std::io::{Seek, SeekFrom, Write};
+#[cfg(feature = "smf")]
+use std::path::Path;
+use crate::reading::Frame;
+use crate::sink::Sink;
+
+/// Cents past the half-way point between notes before switching note, so a
+/// pitch sitting on the boundary doesn't retrigger all the time.
+const HYSTERESIS: i32 = 15;
+
+/// Seconds without a reading before the current note is released.
+const RELEASE: f64 = 0.25;
+
+/// Quietest RMS level in dBFS mapped to velocity 1, 0 dBFS is 127.
+const VELOCITY_FLOOR_DB: f32 = -60.0;
+
+/// Ticks per quarter note in the Standard MIDI File.
+#[cfg(feature = "smf")]
+const DIVISION: u16 = 480;
+
+/// Microseconds per quarter note, i.e. 120 bpm, so a tick is 1/960th of a second.
+#[cfg(feature = "smf")]
+const TEMPO: u32 = 500_000;
+
+/// The meta event every track ends with, at no time after the last event.
+#[cfg(feature = "smf")]
+const END_OF_TRACK: [u8; 4] = [0x00, 0xFF, 0x2F, 0x00];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiMessage {
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+    /// Signed bend from -8192 to 8191, 0 being no bend.
+    PitchBend(i16),
+    Control { controller: u8, value: u8 }
+}
+
+impl MidiMessage {
+
+    /// The message as sent over the wire on `channel`.
+    pub fn to_bytes(&self, channel: u8) -> Vec<u8> {
+        let channel = channel & 0x0F;
+        match *self {
+            MidiMessage::NoteOn { note, velocity } => vec![0x90 | channel, note & 0x7F, velocity & 0x7F],
+            MidiMessage::NoteOff { note } => vec![0x80 | channel, note & 0x7F, 0],
+            MidiMessage::PitchBend(bend) => {
+                let value = (bend as i32 + 8192) as u16;
+                vec![0xE0 | channel, (value & 0x7F) as u8, (value >> 7) as u8]
+            }
+            MidiMessage::Control { controller, value } => vec![0xB0 | channel, controller & 0x7F, value & 0x7F]
+        }
+    }
+}
+
+/// Turns a stream of readings into monophonic note and pitch bend messages.
+#[derive(Clone, Debug)]
+pub struct PitchToMidi {
+    /// Pitch bend range in semitones either way.
+    bend_range: u8,
+    note: Option<u8>,
+    bend: i16,
+    last_time: f64
+}
+
+impl PitchToMidi {
+
+    pub fn new(bend_range: u8) -> Self {
+        PitchToMidi { bend_range, note: None, bend: 0, last_time: 0.0 }
+    }
+
+    /// Registered parameter messages telling the receiver our pitch bend range.
+    pub fn setup(&self) -> Vec<MidiMessage> {
+        [(101, 0), (100, 0), (6, self.bend_range), (38, 0), (101, 127), (100, 127)].iter()
+            .map(|&(controller, value)| MidiMessage::Control { controller, value })
+            .collect()
+    }
+
+    pub fn frame(&mut self, frame: &Frame) -> Vec<MidiMessage> {
+        self.last_time = frame.reading.timestamp;
+        // The frame's note may be a preset's string rather than the nearest
+        // semitone, so go back to the exact pitch in cents.
+        let pitch = frame.midi_note * 100 + frame.cents;
+        let note = match self.note {
+            Some(n) if (pitch - n as i32 * 100).abs() <= 50 + HYSTERESIS => n,
+            _ => match (pitch as f32 / 100.0).round() as i32 {
+                n @ 0..=127 => n as u8,
+                _ => return self.stop()
+            }
+        };
+
+        let range = self.bend_range as i32 * 100;
+        let cents = (pitch - note as i32 * 100).clamp(-range, range);
+        let bend = if range == 0 { 0 } else { (cents * 8192 / range).clamp(-8192, 8191) as i16 };
+
+        let mut messages = Vec::new();
+        if self.note != Some(note) {
+            messages.extend(self.stop());
+            // Bend first so the note doesn't start out of tune.
+            messages.push(MidiMessage::PitchBend(bend));
+            messages.push(MidiMessage::NoteOn { note, velocity: velocity(frame.reading.rms) });
+            self.note = Some(note);
+        } else if bend != self.bend {
+            messages.push(MidiMessage::PitchBend(bend));
+        }
+        self.bend = bend;
+        messages
+    }
+
+    /// Release the note once readings have stopped for a while.
+    pub fn tick(&mut self, now: f64) -> Vec<MidiMessage> {
+        if now - self.last_time > RELEASE {
+            self.stop()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn stop(&mut self) -> Vec<MidiMessage> {
+        match self.note.take() {
+            Some(note) => vec![MidiMessage::NoteOff { note }],
+            None => Vec::new()
+        }
+    }
+
+    pub fn last_time(&self) -> f64 {
+        self.last_time
+    }
+}
+
+fn velocity(rms: f32) -> u8 {
+    let db = 20.0 * rms.max(1e-9).log10();
+    (1.0 + (db - VELOCITY_FLOOR_DB) / -VELOCITY_FLOOR_DB * 126.0).clamp(1.0, 127.0) as u8
+}
+
+/// Where MIDI messages go, along with the time on the readings' clock.
+pub trait MidiOut: Send {
+    fn send(&mut self, time: f64, message: MidiMessage) -> io::Result<()>;
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink converting readings to MIDI.
+pub struct MidiSink<O: MidiOut> {
+    converter: PitchToMidi,
+    out: O,
+    started: bool
+}
+
+impl<O: MidiOut> MidiSink<O> {
+
+    pub fn new(out: O, bend_range: u8) -> Self {
+        MidiSink { converter: PitchToMidi::new(bend_range), out, started: false }
+    }
+
+    fn send_all(&mut self, time: f64, messages: Vec<MidiMessage>) -> io::Result<()> {
+        messages.into_iter().try_for_each(|m| self.out.send(time, m))
+    }
+}
+
+impl<O: MidiOut> Sink for MidiSink<O> {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        let time = frame.reading.timestamp;
+        if !self.started {
+            self.started = true;
+            let setup = self.converter.setup();
+            self.send_all(time, setup)?;
+        }
+        let messages = self.converter.frame(frame);
+        self.send_all(time, messages)
+    }
+
+    fn tick(&mut self, now: f64) -> io::Result<()> {
+        let messages = self.converter.tick(now);
+        self.send_all(now, messages)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let time = self.converter.last_time();
+        let messages = self.converter.stop();
+        self.send_all(time, messages)?;
+        self.out.finish()
+    }
+}
+
+/// Writes messages to a format 0 Standard MIDI File as they come. The track
+/// always ends with an end of track event and its length is kept up to date, so
+/// the file is complete however the tuner stops.
+#[cfg(feature = "smf")]
+pub struct SmfWriter {
+    file: File,
+    channel: u8,
+    /// Time of the first message, tick 0.
+    start: Option<f64>,
+    last_tick: u32,
+    track_len: u32
+}
+
+#[cfg(feature = "smf")]
+impl SmfWriter {
+
+    pub fn create<P: AsRef<Path>>(path: P, channel: u8) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(22);
+        header.extend_from_slice(b"MThd");
+        header.extend_from_slice(&6u32.to_be_bytes());
+        header.extend_from_slice(&0u16.to_be_bytes());
+        header.extend_from_slice(&1u16.to_be_bytes());
+        header.extend_from_slice(&DIVISION.to_be_bytes());
+        header.extend_from_slice(b"MTrk");
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&END_OF_TRACK);
+        let mut smf = SmfWriter { file: File::create(path)?, channel, start: None, last_tick: 0, track_len: 4 };
+        smf.file.write_all(&header)?;
+        // Tempo, so a tick always lasts the same time whatever the player assumes.
+        let mut tempo = vec![0xFF, 0x51, 0x03];
+        tempo.extend_from_slice(&TEMPO.to_be_bytes()[1..]);
+        smf.append(0, &tempo)?;
+        Ok(smf)
+    }
+
+    /// Add an event `delta` ticks after the last one in place of the end of
+    /// track, which goes back on after it.
+    fn append(&mut self, delta: u32, event: &[u8]) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(event.len() + 8);
+        write_var_len(&mut bytes, delta);
+        bytes.extend_from_slice(event);
+        bytes.extend_from_slice(&END_OF_TRACK);
+        self.file.seek(SeekFrom::End(-(END_OF_TRACK.len() as i64)))?;
+        self.file.write_all(&bytes)?;
+        self.track_len += bytes.len() as u32 - END_OF_TRACK.len() as u32;
+        self.file.seek(SeekFrom::Start(18))?;
+        self.file.write_all(&self.track_len.to_be_bytes())
+    }
+}
+
+#[cfg(feature = "smf")]
+impl MidiOut for SmfWriter {
+    fn send(&mut self, time: f64, message: MidiMessage) -> io::Result<()> {
+        let ticks_per_second = DIVISION as f64 * 1_000_000.0 / TEMPO as f64;
+        let start = *self.start.get_or_insert(time);
+        let tick = (((time - start) * ticks_per_second).round() as u32).max(self.last_tick);
+        let delta = tick - self.last_tick;
+        self.last_tick = tick;
+        self.append(delta, &message.to_bytes(self.channel))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+/// MIDI variable length quantity, 7 bits per byte with the high bit set on all but the last.
+#[cfg(feature = "smf")]
+fn write_var_len(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+/// A virtual port on the ALSA sequencer that other programs can connect to.
+#[cfg(all(target_os = "linux", feature = "midi-port"))]
+pub struct AlsaPort {
+    seq: alsa::seq::Seq,
+    port: i32,
+    channel: u8
+}
+
+#[cfg(all(target_os = "linux", feature = "midi-port"))]
+impl AlsaPort {
+
+    pub fn open(channel: u8) -> io::Result<Self> {
+        use alsa::seq::{PortCap, PortType, Seq};
+        use std::ffi::CString;
+
+        let seq = Seq::open(None, Some(alsa::Direction::Playback), false).map_err(alsa_error)?;
+        let name = CString::new("clituner")
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        seq.set_client_name(&name).map_err(alsa_error)?;
+        let port = seq.create_simple_port(
+            &name,
+            PortCap::READ | PortCap::SUBS_READ,
+            PortType::MIDI_GENERIC | PortType::APPLICATION
+        ).map_err(alsa_error)?;
+        Ok(AlsaPort { seq, port, channel })
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "midi-port"))]
+impl MidiOut for AlsaPort {
+    fn send(&mut self, _time: f64, message: MidiMessage) -> io::Result<()> {
+        use alsa::seq::{EvCtrl, EvNote, Event, EventType};
+
+        let channel = self.channel;
+        let mut event = match message {
+            MidiMessage::NoteOn { note, velocity } =>
+                Event::new(EventType::Noteon, &EvNote { channel, note, velocity, ..EvNote::default() }),
+            MidiMessage::NoteOff { note } =>
+                Event::new(EventType::Noteoff, &EvNote { channel, note, ..EvNote::default() }),
+            MidiMessage::PitchBend(bend) =>
+                Event::new(EventType::Pitchbend, &EvCtrl { channel, param: 0, value: bend as i32 }),
+            MidiMessage::Control { controller, value } =>
+                Event::new(EventType::Controller, &EvCtrl { channel, param: controller as u32, value: value as i32 })
+        };
+        event.set_source(self.port);
+        event.set_subs();
+        event.set_direct();
+        self.seq.event_output(&mut event).map_err(alsa_error)?;
+        self.seq.drain_output().map_err(alsa_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "midi-port"))]
+fn alsa_error(err: alsa::Error) -> io::Error {
+    io::Error::other(format!("ALSA sequencer: {}", err))
+}
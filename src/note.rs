@@ -0,0 +1,56 @@
+use crate::math::{log2, powf, round};
+
+pub const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// MIDI note number of A4, the note the reference pitch is given for.
+pub const A4: i32 = 69;
+
+/// Frequency of a MIDI note in equal temperament, given the frequency of A4.
+pub fn midi_to_freq(midi_note: i32, reference: f32) -> f32 {
+    powf(2.0, (midi_note - A4) as f32 / 12.0) * reference
+}
+
+/// Deviation of `freq` from `target` in cents.
+pub fn cents_off(freq: f32, target: f32) -> i32 {
+    round(1200.0 * log2(freq / target)) as i32
+}
+
+/// Returns the nearest MIDI note number and the deviation from it in cents.
+pub fn freq_to_note(freq: f32, reference: f32) -> (i32, i32) {
+    let note_with_cents = 12.0 * log2(freq / reference) + A4 as f32;
+    let midi_note = round(note_with_cents) as i32;
+    (midi_note, cents_off(freq, midi_to_freq(midi_note, reference)))
+}
+
+pub fn pitch_class(midi_note: i32) -> usize {
+    midi_note.rem_euclid(12) as usize
+}
+
+pub fn octave(midi_note: i32) -> i32 {
+    midi_note.div_euclid(12) - 1
+}
+
+/// Scientific pitch notation, e.g. `"E2"` for MIDI note 40.
+#[cfg(feature = "std")]
+pub fn note_name(midi_note: i32) -> String {
+    format!("{}{}", NOTE_NAMES[pitch_class(midi_note)], octave(midi_note))
+}
+
+/// MIDI note number from scientific pitch notation such as `"A4"`, `"C#3"` or `"Bb2"`.
+pub fn parse_note(name: &str) -> Option<i32> {
+    let mut chars = name.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let mut class = NOTE_NAMES.iter().position(|n| n.len() == 1 && n.starts_with(letter))? as i32;
+    let rest = chars.as_str();
+    let rest = if let Some(rest) = rest.strip_prefix('#') {
+        class += 1;
+        rest
+    } else if let Some(rest) = rest.strip_prefix('b') {
+        class -= 1;
+        rest
+    } else {
+        rest
+    };
+    let midi_note = (rest.parse::<i32>().ok()? + 1) * 12 + class;
+    if (0..=127).contains(&midi_note) { Some(midi_note) } else { None }
+}
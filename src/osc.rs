@@ -0,0 +1,99 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use crate::reading::Frame;
+use crate::sink::Sink;
+
+/// An OSC 1.0 argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Str(String)
+}
+
+impl OscArg {
+    fn type_tag(&self) -> char {
+        match self {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::Str(_) => 's'
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>
+}
+
+impl OscMessage {
+
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        OscMessage { address: String::from(address), args }
+    }
+
+    /// The message as a packet: the address, the type tag string and the
+    /// arguments, with strings null terminated and padded to four bytes and
+    /// numbers big endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_str(&mut bytes, &self.address);
+        let tags: String = std::iter::once(',').chain(self.args.iter().map(OscArg::type_tag)).collect();
+        write_str(&mut bytes, &tags);
+        for arg in self.args.iter() {
+            match arg {
+                OscArg::Int(i) => bytes.extend_from_slice(&i.to_be_bytes()),
+                OscArg::Float(f) => bytes.extend_from_slice(&f.to_be_bytes()),
+                OscArg::Str(s) => write_str(&mut bytes, s)
+            }
+        }
+        bytes
+    }
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    bytes.extend_from_slice(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    bytes.extend(std::iter::repeat_n(0, padding));
+}
+
+/// The messages sent for each reading.
+pub fn frame_messages(frame: &Frame) -> Vec<OscMessage> {
+    vec![
+        OscMessage::new("/tuner/pitch", vec![OscArg::Float(frame.reading.frequency)]),
+        OscMessage::new("/tuner/note", vec![OscArg::Int(frame.midi_note)]),
+        OscMessage::new("/tuner/cents", vec![OscArg::Float(frame.cents as f32)]),
+        OscMessage::new("/tuner/confidence", vec![OscArg::Float(frame.reading.confidence)])
+    ]
+}
+
+/// Sends readings as OSC messages over UDP.
+pub struct OscSender {
+    socket: UdpSocket,
+    target: SocketAddr
+}
+
+impl OscSender {
+
+    pub fn new<A: ToSocketAddrs>(target: A) -> io::Result<Self> {
+        let target = target.to_socket_addrs()?.next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send OSC to"))?;
+        let local: SocketAddr = if target.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        Ok(OscSender { socket: UdpSocket::bind(local)?, target })
+    }
+
+    pub fn send(&self, message: &OscMessage) -> io::Result<()> {
+        match self.socket.send_to(&message.to_bytes(), self.target) {
+            // Nobody listening yet is fine, they may start later.
+            Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(()),
+            result => result.map(|_| ())
+        }
+    }
+}
+
+impl Sink for OscSender {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        frame_messages(frame).iter().try_for_each(|m| self.send(m))
+    }
+}
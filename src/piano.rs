@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use crate::note::{cents_off, freq_to_note, midi_to_freq};
+
+/// MIDI notes of the 88 keys, A0 to C8.
+pub const PIANO_KEYS: (i32, i32) = (21, 108);
+
+/// Below this key octaves are tuned 4:2, matching the fourth partial of the
+/// lower note to the second of the upper one, above it they're tuned 2:1.
+const WIDE_OCTAVES_BELOW: i32 = 48;
+
+/// Highest partial looked for when measuring inharmonicity.
+const MAX_PARTIAL: usize = 12;
+
+/// Largest believable inharmonicity coefficient, anything above is a bad measurement.
+const MAX_INHARMONICITY: f32 = 0.05;
+
+/// How far below zero a measured coefficient can be before it's thrown out.
+const MEASUREMENT_ERROR: f32 = 1e-4;
+
+/// A typical inharmonicity coefficient for each key, rising steeply into the
+/// treble as strings get shorter and stiffer and a little into the bass.
+pub fn typical_inharmonicity(key: i32) -> f32 {
+    1.0e-4 * (0.083 * (key - 48) as f32).exp() + 1.5e-4 * (-0.06 * (key - PIANO_KEYS.0) as f32).exp()
+}
+
+/// An approximation of the Railsback curve, the average deviation in cents of
+/// well tuned pianos from equal temperament.
+pub fn railsback(key: i32) -> f32 {
+    let octaves = (key - 69) as f32 / 12.0;
+    if octaves >= 0.0 { 2.5 * octaves * octaves } else { -1.8 * octaves * octaves }
+}
+
+/// Cents an octave starting on a string with inharmonicity `b` is widened by
+/// so the partials that have to agree do.
+fn octave_stretch(key: i32, b: f32) -> f32 {
+    let (lower, upper) = if key < WIDE_OCTAVES_BELOW { (4.0, 2.0) } else { (2.0, 1.0) };
+    600.0 * ((1.0 + lower * lower * b) / (1.0 + upper * upper * b)).log2()
+}
+
+/// Stretched targets for each key, from the Railsback curve until some notes
+/// have been measured and from their inharmonicity after that.
+#[derive(Clone, Debug)]
+pub struct Stretch {
+    /// Scale applied to the Railsback curve, 1 for a typical piano.
+    amount: f32,
+    reference: f32,
+    /// Mean inharmonicity measured for each key, and how many measurements went into it.
+    measured: BTreeMap<i32, (f32, u32)>
+}
+
+impl Stretch {
+
+    pub fn new(amount: f32, reference: f32) -> Self {
+        Stretch { amount, reference, measured: BTreeMap::new() }
+    }
+
+    pub fn set_reference(&mut self, reference: f32) {
+        self.reference = reference;
+    }
+
+    /// Number of keys measured so far.
+    pub fn measured(&self) -> usize {
+        self.measured.len()
+    }
+
+    /// Add a measurement of the note around `freq`.
+    pub fn record(&mut self, freq: f32, b: f32) {
+        let key = freq_to_note(freq, self.reference).0;
+        if key < PIANO_KEYS.0 || key > PIANO_KEYS.1 {
+            return
+        }
+        let (mean, count) = self.measured.entry(key).or_insert((0.0, 0));
+        *count += 1;
+        *mean += (b - *mean) / *count as f32;
+    }
+
+    /// Inharmonicity of `key`, the typical curve scaled to pass through the
+    /// measured keys on either side.
+    pub fn inharmonicity(&self, key: i32) -> f32 {
+        let scale = |k: i32, b: f32| (b.max(1e-7) / typical_inharmonicity(k)).ln();
+        let below = self.measured.range(..=key).next_back();
+        let above = self.measured.range(key..).next();
+        let log_scale = match (below, above) {
+            (Some((&k0, &(b0, _))), Some((&k1, &(b1, _)))) if k0 != k1 => {
+                let t = (key - k0) as f32 / (k1 - k0) as f32;
+                scale(k0, b0) * (1.0 - t) + scale(k1, b1) * t
+            }
+            (Some((&k, &(b, _))), _) | (None, Some((&k, &(b, _)))) => scale(k, b),
+            (None, None) => 0.0
+        };
+        typical_inharmonicity(key) * log_scale.exp()
+    }
+
+    /// Deviation of `key` from equal temperament in cents, A4 staying put.
+    pub fn offset(&self, key: i32) -> f32 {
+        if self.measured.is_empty() {
+            return self.amount * railsback(key)
+        }
+        // Spread each octave's stretch over its twelve semitones, working out from A4.
+        if key >= 69 {
+            (69..key).map(|k| octave_stretch(k, self.inharmonicity(k)) / 12.0).sum()
+        } else {
+            -(key..69).map(|k| octave_stretch(k, self.inharmonicity(k)) / 12.0).sum::<f32>()
+        }
+    }
+
+    /// Stretched frequency of `key`.
+    pub fn frequency(&self, key: i32) -> f32 {
+        midi_to_freq(key, self.reference) * 2.0f32.powf(self.offset(key) / 1200.0)
+    }
+
+    /// The nearest key to `freq` and the deviation from its stretched target in cents.
+    pub fn target(&self, freq: f32) -> (i32, i32) {
+        let nearest = freq_to_note(freq, self.reference).0;
+        // Stretching can put a neighbouring key's target closer than the nearest one.
+        [nearest - 1, nearest + 1].iter()
+            .map(|&key| (key, cents_off(freq, self.frequency(key))))
+            .fold((nearest, cents_off(freq, self.frequency(nearest))), |best, candidate| {
+                if candidate.1.abs() < best.1.abs() { candidate } else { best }
+            })
+    }
+}
+
+/// Measures inharmonicity on its own thread and records it in a `Stretch`, so
+/// whoever has the samples, like the audio callback, only has to copy them.
+pub struct Measurer {
+    sender: SyncSender<(Vec<f32>, f32, f32)>,
+    /// The one buffer passed back and forth, here while the thread is idle.
+    idle: Receiver<Vec<f32>>
+}
+
+impl Measurer {
+
+    pub fn new(stretch: Arc<Mutex<Stretch>>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<(Vec<f32>, f32, f32)>(1);
+        let (done, idle) = mpsc::sync_channel(1);
+        let _ = done.send(Vec::new());
+        thread::spawn(move || {
+            for (samples, sample_rate, freq) in receiver {
+                if let Some(b) = measure_inharmonicity(&samples, sample_rate, freq) {
+                    stretch.lock().unwrap_or_else(|e| e.into_inner()).record(freq, b);
+                }
+                // Only fails once the measurer is dropped, which is also when we stop.
+                let _ = done.send(samples);
+            }
+        });
+        Measurer { sender, idle }
+    }
+
+    /// Measure a note sounding at about `freq`, unless the last one is still
+    /// being measured. Never blocks.
+    pub fn measure(&self, samples: &[f32], sample_rate: f32, freq: f32) {
+        if let Ok(mut buffer) = self.idle.try_recv() {
+            buffer.clear();
+            buffer.extend_from_slice(samples);
+            let _ = self.sender.try_send((buffer, sample_rate, freq));
+        }
+    }
+}
+
+/// Estimate the inharmonicity coefficient B of a string sounding at about
+/// `freq`, from how far its partials are sharp of whole multiples of the
+/// fundamental: partial k sits at k f0 sqrt(1 + B k²).
+pub fn measure_inharmonicity(samples: &[f32], sample_rate: f32, freq: f32) -> Option<f32> {
+    let len = samples.len() as f32;
+    let windowed: Vec<f32> = samples.iter().enumerate()
+        .map(|(n, s)| s * (0.5 - 0.5 * (2.0 * PI * n as f32 / len).cos()))
+        .collect();
+    let bin = sample_rate / len;
+    let first = find_peak(&windowed, sample_rate, freq - bin, freq + bin)?;
+
+    let mut total = 0.0;
+    let mut weights = 0.0;
+    for k in 2..=MAX_PARTIAL {
+        let k = k as f32;
+        // Look where the estimate so far says the partial should be.
+        let b = if weights > 0.0 { total / weights } else { 0.0 };
+        let expected = k * first * ((1.0 + b * k * k) / (1.0 + b)).sqrt();
+        let width = first / 4.0;
+        if expected + width > sample_rate / 2.0 {
+            break
+        }
+        if let Some(partial) = find_peak(&windowed, sample_rate, expected - width, expected + width) {
+            let ratio = (partial / (k * first)).powi(2);
+            // Higher partials move further for the same B so count for more.
+            total += (ratio - 1.0) / (k * k - ratio) * k * k;
+            weights += k * k;
+        }
+    }
+    if weights == 0.0 {
+        return None
+    }
+    // A little below zero is a harmonic string measured with some error.
+    match total / weights {
+        b if (-MEASUREMENT_ERROR..=MAX_INHARMONICITY).contains(&b) => Some(b.max(0.0)),
+        _ => None
+    }
+}
+
+/// Frequency of the strongest peak between `low` and `high`, refined by
+/// fitting a parabola through the log magnitudes either side. None if the
+/// peak is at the edge of the range, so there is no partial there.
+fn find_peak(windowed: &[f32], sample_rate: f32, low: f32, high: f32) -> Option<f32> {
+    let step = sample_rate / windowed.len() as f32 / 4.0;
+    let count = ((high - low) / step).ceil() as usize + 1;
+    let levels: Vec<f32> = (0..count).map(|i| magnitude(windowed, sample_rate, low + i as f32 * step).ln()).collect();
+    let (best, _) = levels.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    if best == 0 || best == count - 1 {
+        return None
+    }
+    let (a, b, c) = (levels[best - 1], levels[best], levels[best + 1]);
+    let offset = 0.5 * (a - c) / (a - 2.0 * b + c);
+    Some(low + (best as f32 + offset) * step)
+}
+
+/// Magnitude of the signal's spectrum at `freq`, by the Goertzel algorithm.
+fn magnitude(windowed: &[f32], sample_rate: f32, freq: f32) -> f32 {
+    let coeff = 2.0 * (2.0 * PI * freq / sample_rate).cos();
+    let (s1, s2) = windowed.iter().fold((0.0f32, 0.0f32), |(s1, s2), x| (x + coeff * s1 - s2, s1));
+    (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(1e-24).sqrt()
+}
@@ -0,0 +1,35 @@
+use crate::note::{cents_off, freq_to_note, midi_to_freq};
+
+/// A set of target notes, one per open string.
+#[derive(Debug, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    /// MIDI note numbers in the order the strings are usually named.
+    pub strings: &'static [i32]
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset { name: "chromatic", strings: &[] },
+    Preset { name: "guitar", strings: &[40, 45, 50, 55, 59, 64] },
+    Preset { name: "bass", strings: &[28, 33, 38, 43] },
+    Preset { name: "ukulele", strings: &[67, 60, 64, 69] },
+    Preset { name: "violin", strings: &[55, 62, 69, 76] },
+    Preset { name: "viola", strings: &[48, 55, 62, 69] },
+    Preset { name: "cello", strings: &[36, 43, 50, 57] }
+];
+
+impl Preset {
+
+    pub fn find(name: &str) -> Option<usize> {
+        PRESETS.iter().position(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The target note for `freq` and the deviation from it in cents. Chromatic
+    /// picks the nearest semitone, otherwise the nearest string is the target.
+    pub fn target(&self, freq: f32, reference: f32) -> (i32, i32) {
+        self.strings.iter()
+            .map(|&s| (s, cents_off(freq, midi_to_freq(s, reference))))
+            .min_by_key(|(_, cents)| cents.abs())
+            .unwrap_or_else(|| freq_to_note(freq, reference))
+    }
+}
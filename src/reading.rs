@@ -0,0 +1,56 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::note::{octave, NOTE_NAMES, pitch_class};
+
+/// The result of analysing one buffer of input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    /// Time of the window's last sample in seconds since the Unix epoch, counted
+    /// by samples from when the first were pushed.
+    pub timestamp: f64,
+    /// Estimated fundamental frequency in Hz.
+    pub frequency: f32,
+    /// How periodic the buffer was, from 0 to 1.
+    pub confidence: f32,
+    /// Root mean square of the buffer's samples.
+    pub rms: f32
+}
+
+/// A reading along with the note it was matched to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub reading: Reading,
+    pub midi_note: i32,
+    pub cents: i32
+}
+
+/// Seconds since the Unix epoch, the clock readings are timestamped with.
+pub fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+impl Reading {
+
+    /// One JSON object on a single line, with the note the reading was matched to.
+    pub fn to_json(&self, midi_note: i32, cents: i32) -> String {
+        format!(
+            "{{\"timestamp\":{:.3},\"frequency\":{:.3},\"confidence\":{:.3},\"note\":\"{}\",\"octave\":{},\"midi\":{},\"cents\":{},\"rms\":{:.5}}}",
+            self.timestamp,
+            self.frequency,
+            self.confidence,
+            NOTE_NAMES[pitch_class(midi_note)],
+            octave(midi_note),
+            midi_note,
+            cents,
+            self.rms
+        )
+    }
+}
@@ -0,0 +1,106 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::note::note_name;
+use crate::reading::Frame;
+use crate::sink::Sink;
+
+pub const CSV_HEADER: &str = "time,frequency,note,cents,rms,confidence";
+
+/// Writes readings to a CSV file, optionally starting a new file every `rotate` rows.
+pub struct CsvRecorder {
+    path: PathBuf,
+    rotate: Option<usize>,
+    append: bool,
+    file: BufWriter<File>,
+    rows: usize,
+    /// Number of the file currently written to, 0 being `path` itself.
+    index: usize
+}
+
+impl CsvRecorder {
+
+    /// Create the file at `path`, or add to the end of it when `append` is set.
+    /// Appending to rotated files carries on from the last of them.
+    pub fn create<P: AsRef<Path>>(path: P, append: bool, rotate: Option<usize>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut index = 0;
+        if append && rotate.is_some() {
+            while rotated_path(&path, index + 1).exists() {
+                index += 1;
+            }
+        }
+        let current = rotated_path(&path, index);
+        let rows = if append && current.exists() { count_rows(&current)? } else { 0 };
+        let file = open(&current, append)?;
+        Ok(CsvRecorder { path, rotate, append, file, rows, index })
+    }
+
+    /// The file currently being written to.
+    pub fn current_path(&self) -> PathBuf {
+        rotated_path(&self.path, self.index)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.index += 1;
+        self.file = open(&rotated_path(&self.path, self.index), self.append)?;
+        self.rows = 0;
+        Ok(())
+    }
+}
+
+impl Sink for CsvRecorder {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.rotate.is_some_and(|max| self.rows >= max) {
+            self.rotate()?;
+        }
+        let r = &frame.reading;
+        writeln!(
+            self.file,
+            "{:.3},{:.3},{},{},{:.5},{:.3}",
+            r.timestamp, r.frequency, note_name(frame.midi_note), frame.cents, r.rms, r.confidence
+        )?;
+        self.rows += 1;
+        // Flushed per row so a crash loses at most the reading in flight.
+        self.file.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Open a CSV file for writing, adding the header unless appending to one that has it already.
+fn open(path: &Path, append: bool) -> io::Result<BufWriter<File>> {
+    let file = if append {
+        OpenOptions::new().create(true).append(true).open(path)?
+    } else {
+        File::create(path)?
+    };
+    let empty = file.metadata()?.len() == 0;
+    let mut file = BufWriter::new(file);
+    if empty {
+        writeln!(file, "{}", CSV_HEADER)?;
+    }
+    Ok(file)
+}
+
+/// Rows in an existing CSV file, not counting the header.
+fn count_rows(path: &Path) -> io::Result<usize> {
+    let contents = fs::read_to_string(path)?;
+    Ok(contents.lines().filter(|line| *line != CSV_HEADER).count())
+}
+
+/// `session.csv` becomes `session.1.csv`, `session.2.csv` and so on.
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf()
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, index)
+    };
+    path.with_file_name(name)
+}
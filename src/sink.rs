@@ -0,0 +1,90 @@
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::reading::{unix_time, Frame};
+
+/// How often sinks get a `tick` while no readings come in.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Somewhere readings are sent besides the display, e.g. a file or the network.
+pub trait Sink: Send {
+    fn write(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// Called regularly while there are no readings, with the current time on
+    /// the readings' clock: the last reading's timestamp plus the time since it
+    /// came in, or the time since the Unix epoch before the first.
+    fn tick(&mut self, _now: f64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once when the tuner stops.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Feeds every frame to a set of sinks on a background thread, so slow
+/// disks or networks never hold up the display.
+pub struct Tap {
+    sender: Option<Sender<Frame>>,
+    handle: Option<JoinHandle<Vec<io::Error>>>
+}
+
+impl Tap {
+
+    pub fn new(mut sinks: Vec<Box<dyn Sink>>) -> Self {
+        if sinks.is_empty() {
+            return Tap { sender: None, handle: None }
+        }
+        let (sender, receiver) = mpsc::channel::<Frame>();
+        let handle = thread::spawn(move || {
+            let mut errors = Vec::new();
+            // The last reading's timestamp and when it came in.
+            let mut last: Option<(f64, Instant)> = None;
+            loop {
+                let received = receiver.recv_timeout(TICK);
+                if received == Err(RecvTimeoutError::Disconnected) {
+                    break;
+                }
+                if let Ok(frame) = &received {
+                    last = Some((frame.reading.timestamp, Instant::now()));
+                }
+                let now = last.map_or_else(unix_time, |(time, at)| time + at.elapsed().as_secs_f64());
+                // A sink that fails is dropped, the others carry on.
+                sinks.retain_mut(|sink| {
+                    let result = match &received {
+                        Ok(frame) => sink.write(frame),
+                        Err(_) => sink.tick(now)
+                    };
+                    result.map_err(|err| errors.push(err)).is_ok()
+                });
+            }
+            for sink in sinks.iter_mut() {
+                if let Err(err) = sink.finish() {
+                    errors.push(err);
+                }
+            }
+            errors
+        });
+        Tap { sender: Some(sender), handle: Some(handle) }
+    }
+
+    pub fn send(&self, frame: Frame) {
+        if let Some(sender) = &self.sender {
+            // Only fails once the thread is gone, and it only stops when we finish.
+            let _ = sender.send(frame);
+        }
+    }
+
+    /// Wait for the sinks to write everything and return the errors they ran into.
+    pub fn finish(mut self) -> Vec<io::Error> {
+        self.sender.take();
+        match self.handle.take() {
+            Some(handle) => handle.join().unwrap_or_else(|_| {
+                vec![io::Error::other("output thread panicked")]
+            }),
+            None => Vec::new()
+        }
+    }
+}
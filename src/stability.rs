@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use crate::reading::Reading;
+
+/// Seconds of a note needed before it's worth analysing.
+const MIN_DURATION: f64 = 1.0;
+
+/// Seconds of the most recent part of a note that are analysed.
+const MAX_DURATION: f64 = 30.0;
+
+/// A gap in the readings this long in seconds ends the note.
+const MAX_GAP: f64 = 0.3;
+
+/// Moving this far in cents from the note's mean starts a new note. Wide
+/// enough for an operatic vibrato.
+const NEW_NOTE_CENTS: f32 = 150.0;
+
+/// Cents the pitch has to swing either side of its trend to count as a vibrato cycle.
+const VIBRATO_HYSTERESIS: f32 = 2.0;
+
+/// Slowest and fastest vibrato in Hz, anything else is just unsteadiness.
+const VIBRATO_RATE: (f32, f32) = (3.0, 12.0);
+
+/// Fewest readings in a cycle of vibrato. At two, half the rate of the
+/// readings, it can't be told from aliasing.
+const MIN_READINGS_PER_CYCLE: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vibrato {
+    /// Cycles per second.
+    pub rate: f32,
+    /// Cents either side of the mean.
+    pub extent: f32
+}
+
+/// How steady a held note is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Analysis {
+    /// Seconds analysed.
+    pub duration: f32,
+    /// Mean pitch in Hz.
+    pub mean: f32,
+    /// Standard deviation in cents.
+    pub deviation: f32,
+    /// Cents per second the pitch is rising, negative when falling.
+    pub drift: f32,
+    pub vibrato: Option<Vibrato>
+}
+
+impl Analysis {
+
+    /// A JSON object, the vibrato fields being null when there's no vibrato.
+    pub fn to_json(&self) -> String {
+        let (rate, extent) = match self.vibrato {
+            Some(v) => (format!("{:.2}", v.rate), format!("{:.1}", v.extent)),
+            None => (String::from("null"), String::from("null"))
+        };
+        format!(
+            "{{\"duration\":{:.2},\"mean\":{:.3},\"deviation\":{:.1},\"drift\":{:.2},\"vibrato_rate\":{},\"vibrato_extent\":{}}}",
+            self.duration, self.mean, self.deviation, self.drift, rate, extent
+        )
+    }
+
+    /// One line for the display.
+    pub fn summary(&self) -> String {
+        let vibrato = match self.vibrato {
+            Some(v) => format!("vibrato {:.1} Hz ±{:.0}¢", v.rate, v.extent),
+            None => String::from("no vibrato")
+        };
+        format!(
+            "held {:.1}s  mean {:.2} Hz  ±{:.1}¢  drift {:+.1}¢/s  {}",
+            self.duration, self.mean, self.deviation, self.drift, vibrato
+        )
+    }
+}
+
+/// Collects the readings of the note being held.
+#[derive(Clone, Debug, Default)]
+pub struct Stability {
+    /// Time and pitch in cents relative to `base`.
+    readings: VecDeque<(f64, f32)>,
+    /// Sum of the cents in `readings`, kept as they come and go.
+    sum: f64,
+    base: f32
+}
+
+impl Stability {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, reading: Reading) {
+        let gap = self.readings.back().is_some_and(|&(t, _)| reading.timestamp - t > MAX_GAP);
+        if gap || self.readings.is_empty() {
+            self.start(reading);
+            return
+        }
+        let cents = 1200.0 * (reading.frequency / self.base).log2();
+        if (cents - self.mean_cents()).abs() > NEW_NOTE_CENTS {
+            self.start(reading);
+            return
+        }
+        self.readings.push_back((reading.timestamp, cents));
+        self.sum += cents as f64;
+        while let Some(&(t, c)) = self.readings.front() {
+            if reading.timestamp - t <= MAX_DURATION {
+                break
+            }
+            self.readings.pop_front();
+            self.sum -= c as f64;
+        }
+    }
+
+    fn start(&mut self, reading: Reading) {
+        self.readings.clear();
+        self.sum = 0.0;
+        self.base = reading.frequency;
+        self.readings.push_back((reading.timestamp, 0.0));
+    }
+
+    fn mean_cents(&self) -> f32 {
+        (self.sum / self.readings.len() as f64) as f32
+    }
+
+    /// The analysis of the note so far, once it's been held long enough.
+    pub fn analysis(&self) -> Option<Analysis> {
+        let (start, end) = (self.readings.front()?.0, self.readings.back()?.0);
+        if end - start < MIN_DURATION {
+            return None
+        }
+        let n = self.readings.len() as f32;
+        let mean = self.mean_cents();
+        let deviation = (self.readings.iter().map(|&(_, c)| (c - mean).powi(2)).sum::<f32>() / n).sqrt();
+
+        // Least squares line through the pitches, its slope being the drift.
+        let mean_time = self.readings.iter().map(|&(t, _)| (t - start) as f32).sum::<f32>() / n;
+        let (covariance, variance) = self.readings.iter().fold((0.0, 0.0), |(cov, var), &(t, c)| {
+            let dt = (t - start) as f32 - mean_time;
+            (cov + dt * (c - mean), var + dt * dt)
+        });
+        let drift = if variance > 0.0 { covariance / variance } else { 0.0 };
+
+        let residuals: Vec<(f64, f32)> = self.readings.iter()
+            .map(|&(t, c)| (t, c - mean - drift * ((t - start) as f32 - mean_time)))
+            .collect();
+        Some(Analysis {
+            duration: (end - start) as f32,
+            mean: self.base * 2.0f32.powf(mean / 1200.0),
+            deviation,
+            drift,
+            vibrato: vibrato(&residuals)
+        })
+    }
+}
+
+/// The rate from the times the detrended pitch swings up through its trend,
+/// and the extent from its RMS as it would be for a sine.
+fn vibrato(residuals: &[(f64, f32)]) -> Option<Vibrato> {
+    let mut rises = Vec::new();
+    let mut low = false;
+    for &(t, r) in residuals {
+        if r < -VIBRATO_HYSTERESIS {
+            low = true;
+        } else if low && r > VIBRATO_HYSTERESIS {
+            low = false;
+            rises.push(t);
+        }
+    }
+    if rises.len() < 3 {
+        return None
+    }
+    let rate = (rises.len() - 1) as f32 / (rises[rises.len() - 1] - rises[0]) as f32;
+    let readings = (residuals.len() - 1) as f32 / (residuals[residuals.len() - 1].0 - residuals[0].0) as f32;
+    if rate < VIBRATO_RATE.0 || rate > VIBRATO_RATE.1 || rate * MIN_READINGS_PER_CYCLE > readings {
+        return None
+    }
+    let rms = (residuals.iter().map(|&(_, r)| r * r).sum::<f32>() / residuals.len() as f32).sqrt();
+    Some(Vibrato { rate, extent: rms * 2.0f32.sqrt() })
+}
@@ -0,0 +1,282 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use clituner::drone::Drone;
+use clituner::note::note_name;
+use clituner::piano::Stretch;
+use clituner::preset::{Preset, PRESETS};
+use clituner::reading::{Frame, Reading};
+use crate::args::{Args, DisplayMode, REFERENCE_RANGE};
+
+pub const KEY_HELP: &str = "q quit  +/- A4  d display  p preset  g gate  space freeze";
+
+/// What the UI has to do after a key press.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    Redraw,
+    Nothing
+}
+
+/// Settings that can be changed with the keyboard while tuning.
+#[derive(Debug)]
+pub struct State {
+    /// Frequency of A4 in Hz.
+    pub reference: f32,
+    pub display: DisplayMode,
+    /// Index into `PRESETS`.
+    pub preset: usize,
+    /// Keep showing the current reading and ignore new ones.
+    pub frozen: bool,
+    /// Shared with the audio thread, which skips the amplitude threshold when unset.
+    gate: Arc<AtomicBool>,
+    /// Shared with the output stream playing it and the input filter notching it out.
+    drone: Option<Arc<Mutex<Drone>>>,
+    /// Piano targets, shared with the audio thread measuring inharmonicity.
+    stretch: Option<Arc<Mutex<Stretch>>>
+}
+
+impl State {
+
+    pub fn new(args: &Args) -> Self {
+        State {
+            reference: args.reference,
+            display: args.display,
+            preset: args.preset,
+            frozen: false,
+            gate: Arc::new(AtomicBool::new(true)),
+            drone: if args.drone {
+                let drone = Drone::new(&PRESETS[args.preset], args.reference, args.drone_step as f64);
+                Some(Arc::new(Mutex::new(drone)))
+            } else {
+                None
+            },
+            stretch: if args.piano {
+                Some(Arc::new(Mutex::new(Stretch::new(args.stretch, args.reference))))
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn gate(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.gate)
+    }
+
+    pub fn gate_enabled(&self) -> bool {
+        self.gate.load(Ordering::Relaxed)
+    }
+
+    pub fn drone(&self) -> Option<Arc<Mutex<Drone>>> {
+        self.drone.clone()
+    }
+
+    pub fn stretch(&self) -> Option<Arc<Mutex<Stretch>>> {
+        self.stretch.clone()
+    }
+
+    fn lock_stretch(&self) -> Option<MutexGuard<'_, Stretch>> {
+        self.stretch.as_ref().map(|s| s.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn lock_drone(&self) -> Option<MutexGuard<'_, Drone>> {
+        self.drone.as_ref().map(|d| d.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn preset(&self) -> &'static Preset {
+        &PRESETS[self.preset]
+    }
+
+    /// The target MIDI note for `freq` and the deviation from it in cents.
+    pub fn target(&self, freq: f32) -> (i32, i32) {
+        match self.lock_stretch() {
+            Some(stretch) => stretch.target(freq),
+            None => self.preset().target(freq, self.reference)
+        }
+    }
+
+    /// Keep everything that depends on the reference in step with it.
+    fn set_reference(&mut self, reference: f32) {
+        self.reference = reference;
+        if let Some(mut drone) = self.lock_drone() {
+            drone.set_reference(reference);
+        }
+        if let Some(mut stretch) = self.lock_stretch() {
+            stretch.set_reference(reference);
+        }
+    }
+
+    pub fn frame(&self, reading: Reading) -> Frame {
+        let (midi_note, cents) = self.target(reading.frequency);
+        Frame { reading, midi_note, cents }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => {
+                self.set_reference((self.reference + 1.0).min(REFERENCE_RANGE.1));
+                Action::Redraw
+            }
+            KeyCode::Char('-') | KeyCode::Down => {
+                self.set_reference((self.reference - 1.0).max(REFERENCE_RANGE.0));
+                Action::Redraw
+            }
+            KeyCode::Char('d') => {
+                self.display = match self.display {
+                    DisplayMode::Full => DisplayMode::Compact,
+                    DisplayMode::Compact => DisplayMode::Full
+                };
+                Action::Redraw
+            }
+            KeyCode::Char('p') => {
+                self.preset = (self.preset + 1) % PRESETS.len();
+                if let Some(mut drone) = self.lock_drone() {
+                    drone.set_preset(self.preset());
+                }
+                Action::Redraw
+            }
+            KeyCode::Char('g') => {
+                self.gate.fetch_xor(true, Ordering::Relaxed);
+                Action::Redraw
+            }
+            KeyCode::Char(' ') | KeyCode::Char('f') => {
+                self.frozen = !self.frozen;
+                Action::Redraw
+            }
+            KeyCode::Char('n') => match self.lock_drone() {
+                Some(mut drone) => {
+                    drone.next();
+                    Action::Redraw
+                }
+                None => Action::Nothing
+            },
+            KeyCode::Char('h') => match self.lock_drone() {
+                Some(mut drone) => {
+                    drone.toggle_hold();
+                    Action::Redraw
+                }
+                None => Action::Nothing
+            },
+            _ => Action::Nothing
+        }
+    }
+
+    /// One line summary of the settings above.
+    pub fn status(&self) -> String {
+        let drone = match self.lock_drone() {
+            Some(drone) => format!(
+                "  drone {}{}",
+                drone.note().map_or_else(|| String::from("off"), note_name),
+                if drone.held() { " held" } else { "" }
+            ),
+            None => String::new()
+        };
+        let mode = match self.lock_stretch() {
+            Some(stretch) if stretch.measured() == 1 => String::from("piano, 1 key measured"),
+            Some(stretch) if stretch.measured() > 1 => format!("piano, {} keys measured", stretch.measured()),
+            Some(_) => String::from("piano, railsback"),
+            None => String::from(self.preset().name)
+        };
+        format!(
+            "A4 {} Hz  {}  gate {}{}{}",
+            self.reference,
+            mode,
+            if self.gate_enabled() { "on" } else { "off" },
+            drone,
+            if self.frozen { "  frozen" } else { "" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crate::args::{Args, DisplayMode};
+    use super::{Action, State};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent { code, modifiers: KeyModifiers::NONE }
+    }
+
+    #[test]
+    fn test_nudge_reference() {
+        let mut state = State::new(&Args { reference: 440.0, ..Args::default() });
+        assert_eq!(state.handle_key(key(KeyCode::Char('+'))), Action::Redraw);
+        assert_eq!(state.reference, 441.0);
+        state.handle_key(key(KeyCode::Down));
+        state.handle_key(key(KeyCode::Char('-')));
+        assert_eq!(state.reference, 439.0);
+        assert_eq!(state.target(439.0), (69, 0));
+    }
+
+    #[test]
+    fn test_nudge_reference_to_the_ends_of_its_range() {
+        let mut state = State::new(&Args { reference: 480.0, ..Args::default() });
+        state.handle_key(key(KeyCode::Char('+')));
+        assert_eq!(state.reference, 480.0);
+
+        let mut state = State::new(&Args { reference: 400.0, ..Args::default() });
+        state.handle_key(key(KeyCode::Char('-')));
+        assert_eq!(state.reference, 400.0);
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut state = State::new(&Args::default());
+        let gate = state.gate();
+        state.handle_key(key(KeyCode::Char('g')));
+        assert!(!state.gate_enabled());
+        assert!(!gate.load(std::sync::atomic::Ordering::Relaxed));
+
+        state.handle_key(key(KeyCode::Char(' ')));
+        assert!(state.frozen);
+
+        state.handle_key(key(KeyCode::Char('d')));
+        assert_eq!(state.display, DisplayMode::Compact);
+
+        state.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(state.preset().name, "guitar");
+    }
+
+    #[test]
+    fn test_quit_keys() {
+        let mut state = State::new(&Args::default());
+        assert_eq!(state.handle_key(key(KeyCode::Char('q'))), Action::Quit);
+        assert_eq!(state.handle_key(key(KeyCode::Esc)), Action::Quit);
+        let ctrl_c = KeyEvent { code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL };
+        assert_eq!(state.handle_key(ctrl_c), Action::Quit);
+        assert_eq!(state.handle_key(key(KeyCode::Char('c'))), Action::Nothing);
+    }
+
+    #[test]
+    fn test_drone_keys() {
+        let mut state = State::new(&Args::default());
+        assert_eq!(state.handle_key(key(KeyCode::Char('n'))), Action::Nothing);
+
+        let mut state = State::new(&Args { drone: true, preset: 4, reference: 440.0, ..Args::default() });
+        let drone = state.drone().unwrap();
+        assert_eq!(state.status(), "A4 440 Hz  violin  gate on  drone G3");
+        assert_eq!(state.handle_key(key(KeyCode::Char('n'))), Action::Redraw);
+        state.handle_key(key(KeyCode::Char('h')));
+        assert_eq!(state.status(), "A4 440 Hz  violin  gate on  drone D4 held");
+
+        state.handle_key(key(KeyCode::Char('+')));
+        assert_eq!(drone.lock().unwrap().frequency(), Some(clituner::note::midi_to_freq(62, 441.0)));
+        state.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(drone.lock().unwrap().note(), Some(48));
+    }
+
+    #[test]
+    fn test_piano_targets() {
+        let mut state = State::new(&Args { piano: true, reference: 440.0, ..Args::default() });
+        assert_eq!(state.status(), "A4 440 Hz  piano, railsback  gate on");
+        assert_eq!(state.target(4186.01), (108, -26));
+
+        state.handle_key(key(KeyCode::Char('+')));
+        assert_eq!(state.target(441.0), (69, 0));
+        state.stretch().unwrap().lock().unwrap().record(441.0, 0.0005);
+        assert_eq!(state.status(), "A4 441 Hz  piano, 1 key measured  gate on");
+    }
+}
@@ -0,0 +1,101 @@
+/// How far off a reading is, independent of the direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Band {
+    InTune,
+    Close,
+    Far
+}
+
+/// Cents deviation limits for each band, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds {
+    pub in_tune: u32,
+    pub close: u32
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds { in_tune: 10, close: 30 }
+    }
+}
+
+impl Thresholds {
+    pub fn band(&self, cents: i32) -> Band {
+        let off = cents.unsigned_abs();
+        if off <= self.in_tune {
+            Band::InTune
+        } else if off <= self.close {
+            Band::Close
+        } else {
+            Band::Far
+        }
+    }
+}
+
+/// Arrow showing which side of the target a reading is on, doubled when far off.
+/// This carries the band on its own, so it is shown in every palette.
+pub fn indicator(band: Band, cents: i32) -> &'static str {
+    match (band, cents < 0) {
+        (Band::InTune, _) => "=",
+        (Band::Close, true) => "<",
+        (Band::Close, false) => ">",
+        (Band::Far, true) => "<<",
+        (Band::Far, false) => ">>"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    /// Red, yellow and green bands.
+    Color,
+    /// Bold and inverse attributes instead of colours, as asked for by `NO_COLOR`.
+    Mono,
+    /// No escape codes at all, for dumb terminals and pipes.
+    Plain
+}
+
+impl Palette {
+
+    /// Pick a palette from the colour choice and the environment.
+    /// See <https://no-color.org> for `NO_COLOR`.
+    pub fn detect(choice: ColorChoice, no_color: Option<&str>, term: Option<&str>, is_tty: bool) -> Self {
+        match choice {
+            ColorChoice::Always => Palette::Color,
+            ColorChoice::Never => Palette::Mono,
+            ColorChoice::Auto => {
+                if !is_tty || term.is_none_or(|t| t.is_empty() || t == "dumb") {
+                    Palette::Plain
+                } else if no_color.is_some_and(|v| !v.is_empty()) {
+                    Palette::Mono
+                } else {
+                    Palette::Color
+                }
+            }
+        }
+    }
+}
+
+/// Everything needed to decide how a reading is displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub thresholds: Thresholds
+}
+
+impl Theme {
+
+    pub fn band(&self, cents: i32) -> Band {
+        self.thresholds.band(cents)
+    }
+
+    pub fn indicator(&self, cents: i32) -> &'static str {
+        indicator(self.band(cents), cents)
+    }
+}
@@ -0,0 +1,141 @@
+use std::f32::consts::PI;
+
+/// Seconds between plucks of the Karplus-Strong string, so it keeps sounding.
+const PLUCK_INTERVAL: f32 = 2.0;
+
+/// How much of the string's energy survives each trip round it.
+const DECAY: f32 = 0.996;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Sawtooth,
+    /// A plucked string, synthesized with Karplus-Strong.
+    Pluck
+}
+
+/// Generates a reference tone one sample at a time.
+#[derive(Clone, Debug)]
+pub struct Tone {
+    waveform: Waveform,
+    frequency: f32,
+    sample_rate: f32,
+    volume: f32,
+    /// Position in the current period, from 0 to 1.
+    phase: f32,
+    /// Delay line holding the last trip round the string.
+    string: Vec<f32>,
+    pos: usize,
+    since_pluck: usize,
+    /// State of the noise generator the string is plucked with.
+    seed: u32
+}
+
+impl Tone {
+
+    pub fn new(waveform: Waveform, frequency: f32, sample_rate: u32, volume: f32) -> Self {
+        let mut tone = Tone {
+            waveform,
+            frequency,
+            sample_rate: sample_rate as f32,
+            volume,
+            phase: 0.0,
+            string: Vec::new(),
+            pos: 0,
+            since_pluck: 0,
+            seed: 0x9E37_79B9
+        };
+        tone.pluck();
+        tone
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Change note, replucking the string so its length matches.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+        self.pluck();
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let step = self.frequency / self.sample_rate;
+        let sample = match self.waveform {
+            Waveform::Sine => (2.0 * PI * self.phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * self.phase - 1.0 - poly_blep(self.phase, step),
+            Waveform::Pluck => self.next_string_sample()
+        };
+        self.phase = (self.phase + step).fract();
+        sample * self.volume
+    }
+
+    /// Fill an interleaved buffer, the same sample going to every channel.
+    pub fn fill(&mut self, out: &mut [f32], channels: usize) {
+        for frame in out.chunks_mut(channels.max(1)) {
+            let sample = self.next_sample();
+            frame.iter_mut().for_each(|s| *s = sample);
+        }
+    }
+
+    /// Fill the string with noise, sized so a trip round it takes one period.
+    fn pluck(&mut self) {
+        if self.waveform != Waveform::Pluck {
+            return
+        }
+        let len = (self.delay().ceil() as usize + 2).max(3);
+        self.string = (0..len).map(|_| self.noise()).collect();
+        self.pos = 0;
+        self.since_pluck = 0;
+    }
+
+    /// The averaging filter adds half a sample, the delay line makes up the rest of the period.
+    fn delay(&self) -> f32 {
+        (self.sample_rate / self.frequency - 0.5).max(1.0)
+    }
+
+    fn next_string_sample(&mut self) -> f32 {
+        self.since_pluck += 1;
+        if self.since_pluck as f32 > PLUCK_INTERVAL * self.sample_rate {
+            self.pluck();
+        }
+        let delay = self.delay();
+        let sample = DECAY * 0.5 * (self.delayed(delay) + self.delayed(delay + 1.0));
+        self.string[self.pos] = sample;
+        self.pos = (self.pos + 1) % self.string.len();
+        sample
+    }
+
+    /// The string's output `delay` samples ago, interpolating between samples.
+    fn delayed(&self, delay: f32) -> f32 {
+        let len = self.string.len();
+        let whole = delay.floor() as usize;
+        let frac = delay - whole as f32;
+        let at = |k: usize| self.string[(self.pos + len - k % len) % len];
+        let (a, b) = (at(whole), at(whole + 1));
+        a + (b - a) * frac
+    }
+
+    /// Uniform noise from -1 to 1, xorshift being plenty for a pluck.
+    fn noise(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// Smooths the sawtooth's jump back down, which would otherwise alias badly on high notes.
+fn poly_blep(phase: f32, step: f32) -> f32 {
+    if phase < step {
+        let t = phase / step;
+        t + t - t * t - 1.0
+    } else if phase > 1.0 - step {
+        let t = (phase - 1.0) / step;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
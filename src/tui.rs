@@ -0,0 +1,313 @@
+use std::io::{self, Write};
+use colored::Color;
+use crossterm::{cursor, execute, terminal, ErrorKind};
+use clituner::error::Error;
+
+/// Display attributes of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub inverse: bool
+}
+
+impl Style {
+
+    pub const fn color(color: Color) -> Self {
+        Style { color: Some(color), bold: false, inverse: false }
+    }
+
+    /// The SGR sequence selecting this style from any other.
+    fn sgr(&self) -> String {
+        let mut codes = String::from("0");
+        if self.bold {
+            codes.push_str(";1");
+        }
+        if self.inverse {
+            codes.push_str(";7");
+        }
+        if let Some(c) = self.color {
+            codes.push(';');
+            codes.push_str(&c.to_fg_str());
+        }
+        format!("\x1B[{}m", codes)
+    }
+}
+
+/// A single character on the screen along with its style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style
+}
+
+impl Cell {
+    pub const BLANK: Cell = Cell { ch: ' ', style: Style { color: None, bold: false, inverse: false } };
+}
+
+/// Double-buffered character grid.
+///
+/// Drawing goes into the back buffer, and `present` only writes the cells
+/// that differ from what was last sent to the terminal, so redrawing a
+/// reading doesn't require clearing the whole screen.
+pub struct Screen<W: Write> {
+    out: W,
+    width: usize,
+    height: usize,
+    front: Vec<Cell>,
+    back: Vec<Cell>,
+    invalidated: bool
+}
+
+impl<W: Write> Screen<W> {
+
+    pub fn new(out: W, width: u16, height: u16) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        Screen {
+            out,
+            width,
+            height,
+            front: vec![Cell::BLANK; width * height],
+            back: vec![Cell::BLANK; width * height],
+            invalidated: true
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[cfg(test)]
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    #[cfg(test)]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Repaint everything on the next `present`, e.g. after something else
+    /// has written to the terminal.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Adapt to a new terminal size. The next `present` repaints everything
+    /// since the terminal's own reflow leaves the old contents unreliable.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width as usize;
+        self.height = height as usize;
+        self.front = vec![Cell::BLANK; self.width * self.height];
+        self.back = vec![Cell::BLANK; self.width * self.height];
+        self.invalidated = true;
+    }
+
+    /// Blank the back buffer.
+    pub fn clear(&mut self) {
+        self.back.iter_mut().for_each(|c| *c = Cell::BLANK)
+    }
+
+    #[cfg(test)]
+    pub fn cell(&self, x: usize, y: usize) -> Option<Cell> {
+        if x < self.width && y < self.height {
+            Some(self.back[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Write a single line of text, clipped to the screen.
+    pub fn put_str(&mut self, x: usize, y: usize, s: &str, style: Style) {
+        if y >= self.height {
+            return
+        }
+        for (i, ch) in s.chars().enumerate() {
+            let col = x + i;
+            if col >= self.width {
+                break;
+            }
+            self.back[y * self.width + col] = Cell { ch, style };
+        }
+    }
+
+    /// Write multi-line text with its top left corner at `(x, y)`.
+    pub fn put_block(&mut self, x: usize, y: usize, block: &str, style: Style) {
+        for (i, line) in block.lines().enumerate() {
+            self.put_str(x, y + i, line, style);
+        }
+    }
+
+    /// Send the cells that changed since the last call to the terminal.
+    pub fn present(&mut self) -> io::Result<()> {
+        let mut buf = String::new();
+        if self.invalidated {
+            buf.push_str("\x1B[0m\x1B[2J");
+            self.front.iter_mut().for_each(|c| *c = Cell::BLANK);
+            self.invalidated = false;
+        }
+
+        // Where the terminal cursor is, if known, and the active style.
+        let mut cursor: Option<(usize, usize)> = None;
+        let mut pen = Style::default();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                let cell = self.back[i];
+                if cell == self.front[i] {
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    buf.push_str(&format!("\x1B[{};{}H", y + 1, x + 1));
+                }
+                if cell.style != pen {
+                    buf.push_str(&cell.style.sgr());
+                    pen = cell.style;
+                }
+                buf.push(cell.ch);
+                // Writing the last column leaves the cursor in a pending-wrap
+                // state that differs between terminals, so forget where it is.
+                cursor = if x + 1 < self.width { Some((x + 1, y)) } else { None };
+            }
+        }
+        if pen != Style::default() {
+            buf.push_str("\x1B[0m");
+        }
+        self.front.copy_from_slice(&self.back);
+        self.out.write_all(buf.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+/// Guard that puts the terminal into raw mode, optionally on the alternate
+/// screen with a hidden cursor, and restores it when dropped.
+pub struct Terminal {
+    alternate: bool
+}
+
+impl Terminal {
+
+    pub fn enter() -> crossterm::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Terminal { alternate: false })
+    }
+
+    pub fn size() -> crossterm::Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    pub fn is_alternate(&self) -> bool {
+        self.alternate
+    }
+
+    pub fn set_alternate(&mut self, alternate: bool) -> crossterm::Result<()> {
+        if alternate && !self.alternate {
+            execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        } else if !alternate && self.alternate {
+            execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+        }
+        self.alternate = alternate;
+        Ok(())
+    }
+}
+
+/// An error from crossterm as one of ours.
+pub fn terminal_error(err: ErrorKind) -> Error {
+    let source = match err {
+        ErrorKind::IoError(err) => err,
+        err => io::Error::other(err.to_string())
+    };
+    Error::io("could not use the terminal", source)
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.set_alternate(false);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use colored::Color;
+    use crossterm::ErrorKind;
+    use super::{terminal_error, Cell, Screen, Style};
+
+    fn take_output(screen: &mut Screen<Vec<u8>>) -> String {
+        let out = String::from_utf8(screen.get_ref().clone()).unwrap();
+        screen.get_mut().clear();
+        out
+    }
+
+    #[test]
+    fn test_first_present_clears_screen() {
+        let mut screen = Screen::new(Vec::new(), 10, 3);
+        screen.put_str(1, 1, "ab", Style::default());
+        screen.present().unwrap();
+        assert_eq!(take_output(&mut screen), "\x1B[0m\x1B[2J\x1B[2;2Hab");
+    }
+
+    #[test]
+    fn test_present_only_writes_changed_cells() {
+        let mut screen = Screen::new(Vec::new(), 10, 3);
+        screen.put_str(0, 0, "hello", Style::default());
+        screen.present().unwrap();
+        take_output(&mut screen);
+
+        screen.clear();
+        screen.put_str(0, 0, "help", Style::color(Color::Green));
+        screen.present().unwrap();
+        let out = take_output(&mut screen);
+        assert_eq!(out, "\x1B[1;1H\x1B[0;32mhelp\x1B[0m ");
+
+        screen.present().unwrap();
+        assert_eq!(take_output(&mut screen), "");
+    }
+
+    #[test]
+    fn test_put_block_is_clipped() {
+        let mut screen = Screen::new(Vec::new(), 4, 2);
+        screen.put_block(2, 0, "abc\ndef\nghi", Style::default());
+        assert_eq!(screen.cell(2, 0), Some(Cell { ch: 'a', style: Style::default() }));
+        assert_eq!(screen.cell(3, 1), Some(Cell { ch: 'e', style: Style::default() }));
+        assert_eq!(screen.cell(4, 0), None);
+        assert_eq!(screen.cell(0, 2), None);
+    }
+
+    #[test]
+    fn test_resize_repaints() {
+        let mut screen = Screen::new(Vec::new(), 4, 2);
+        screen.put_str(0, 0, "ab", Style::default());
+        screen.present().unwrap();
+        take_output(&mut screen);
+
+        screen.resize(6, 3);
+        assert_eq!((screen.width(), screen.height()), (6, 3));
+        screen.put_str(0, 2, "ab", Style::default());
+        screen.present().unwrap();
+        assert_eq!(take_output(&mut screen), "\x1B[0m\x1B[2J\x1B[3;1Hab");
+    }
+
+    #[test]
+    fn test_present_attributes() {
+        let mut screen = Screen::new(Vec::new(), 4, 1);
+        screen.present().unwrap();
+        take_output(&mut screen);
+
+        screen.put_str(0, 0, "ok", Style { bold: true, inverse: true, ..Style::default() });
+        screen.present().unwrap();
+        assert_eq!(take_output(&mut screen), "\x1B[1;1H\x1B[0;1;7mok\x1B[0m");
+    }
+
+    #[test]
+    fn test_terminal_error() {
+        let err = terminal_error(ErrorKind::IoError(io::Error::other("no tty")));
+        assert_eq!(err.to_string(), "could not use the terminal: no tty");
+        assert_eq!(err.exit_code(), 1);
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::bac::conf::{Window, CONFIG};
+use crate::bac::stream::StreamingBitstream;
+use crate::bac::wide::Simd;
+use crate::drone::{Drone, DroneFilter};
+use crate::piano::{Measurer, Stretch};
+use crate::reading::{rms, unix_time, Reading};
+
+/// Lowest confidence a reading needs before it's used to measure a piano string.
+const MEASURE_CONFIDENCE: f32 = 0.8;
+
+pub fn linear_to_db(freq: f32) -> f32 {
+    20.0 * freq.abs().log10()
+}
+
+/// Pitch detection over a stream of mono samples at the sample rate of its
+/// `Window`, wherever they come from. Windows overlap, a reading being taken
+/// every `hop` samples.
+pub struct Tuner {
+    stream: StreamingBitstream<Simd>,
+    hop: usize,
+    /// Samples short of a hop.
+    pending: Vec<f32>,
+    /// When the first sample came in, in seconds since the Unix epoch, and
+    /// how many have been analysed since. Readings are timed by the samples
+    /// rather than when they're worked out, which is in bursts.
+    start: Option<f64>,
+    analysed: u64,
+    /// Skip quiet buffers while set.
+    gate: Arc<AtomicBool>,
+    measurer: Option<Measurer>,
+    notch: Option<(Arc<Mutex<Drone>>, DroneFilter)>
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tuner {
+
+    pub fn new() -> Self {
+        Tuner {
+            stream: StreamingBitstream::new(),
+            hop: default_hop(&Window::default()),
+            pending: Vec::with_capacity(CONFIG.buff_size),
+            start: None,
+            analysed: 0,
+            gate: Arc::new(AtomicBool::new(true)),
+            measurer: None,
+            notch: None
+        }
+    }
+
+    /// Detect pitches within `window` rather than the default range, e.g. to run
+    /// a bass and a treble tuner side by side.
+    pub fn with_window(mut self, window: Window) -> Self {
+        self.stream = StreamingBitstream::with_window(window);
+        self.hop = default_hop(&window);
+        self.pending = Vec::with_capacity(window.buff_size);
+        self
+    }
+
+    pub fn window(&self) -> &Window {
+        self.stream.window()
+    }
+
+    /// Take a reading every `hop` samples rather than every eighth of the window.
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Share the noise gate switch, so it can be turned off from elsewhere.
+    pub fn with_gate(mut self, gate: Arc<AtomicBool>) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Measure the inharmonicity of clear notes into `stretch`, on a thread of
+    /// its own.
+    pub fn with_stretch(mut self, stretch: Arc<Mutex<Stretch>>) -> Self {
+        self.measurer = Some(Measurer::new(stretch));
+        self
+    }
+
+    /// Filter out whatever `drone` is playing before listening.
+    pub fn with_notch(mut self, drone: Arc<Mutex<Drone>>, sample_rate: u32) -> Self {
+        self.notch = Some((drone, DroneFilter::new(sample_rate)));
+        self
+    }
+
+    /// Add a block of samples, returning a reading for each hop with a pitch in
+    /// the window up to it, once there's a full window.
+    pub fn push_samples(&mut self, samples: &[f32]) -> Vec<Reading> {
+        let sample_rate = self.window().samples_per_second as f64;
+        self.start.get_or_insert_with(|| unix_time() - samples.len() as f64 / sample_rate);
+        match &mut self.notch {
+            Some((drone, filter)) => {
+                filter.set_frequency(drone.lock().unwrap_or_else(|e| e.into_inner()).frequency());
+                self.pending.extend(samples.iter().map(|&s| filter.process(s)));
+            }
+            None => self.pending.extend_from_slice(samples)
+        }
+
+        let mut readings = Vec::new();
+        let mut start = 0;
+        while self.pending.len() - start >= self.hop {
+            let estimate = self.stream.push(&self.pending[start..start + self.hop]);
+            start += self.hop;
+            self.analysed += self.hop as u64;
+            readings.extend(estimate.and_then(|estimate| self.analyse(estimate)));
+        }
+        self.pending.drain(..start);
+        readings
+    }
+
+    fn analyse(&mut self, (frequency, confidence): (f32, f32)) -> Option<Reading> {
+        let slice = self.stream.samples();
+        let rms = rms(slice);
+        if self.gate.load(Ordering::Relaxed) && linear_to_db(rms) <= CONFIG.amp_threshold {
+            return None
+        }
+        if let Some(measurer) = self.measurer.as_ref().filter(|_| confidence >= MEASURE_CONFIDENCE) {
+            measurer.measure(slice, self.stream.window().samples_per_second as f32, frequency);
+        }
+        let sample_rate = self.stream.window().samples_per_second as f64;
+        let timestamp = self.start? + self.analysed as f64 / sample_rate;
+        Some(Reading { timestamp, frequency, confidence, rms })
+    }
+}
+
+/// An eighth of the window, short enough for the stream to update the
+/// correlation rather than rebuild it.
+fn default_hop(window: &Window) -> usize {
+    (window.buff_size / 8).max(1)
+}
@@ -0,0 +1,164 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::Duration;
+use crate::reading::Frame;
+use crate::sink::Sink;
+use crate::theme::Thresholds;
+
+/// How long a client gets to send its request, or to take an event, before it's dropped.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Most connections handled at once, each having a thread.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Most bytes of a request read, the request line and headers together.
+const MAX_REQUEST: usize = 8192;
+
+/// Events queued for a client before it's dropped for not keeping up.
+const EVENT_QUEUE: usize = 64;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>clituner</title>
+<style>
+  body { background: #111; color: #eee; font-family: sans-serif; text-align: center; margin-top: 15vh; }
+  #note { font-size: 30vmin; font-weight: bold; }
+  #cents { font-size: 8vmin; }
+  #freq { font-size: 4vmin; color: #888; }
+  .in-tune { color: #4c4; } .close { color: #dd4; } .far { color: #d44; }
+</style>
+</head>
+<body>
+<div id="note">-</div>
+<div id="cents"></div>
+<div id="freq">waiting for a note</div>
+<script>
+  const note = document.getElementById("note");
+  const cents = document.getElementById("cents");
+  const freq = document.getElementById("freq");
+  new EventSource("/events").onmessage = (e) => {
+    const r = JSON.parse(e.data);
+    const off = Math.abs(r.cents);
+    note.textContent = r.note + r.octave;
+    note.className = off <= IN_TUNE ? "in-tune" : off <= CLOSE ? "close" : "far";
+    cents.textContent = (r.cents > 0 ? "+" : "") + r.cents + "¢";
+    freq.textContent = r.frequency.toFixed(2) + " Hz";
+  };
+</script>
+</body>
+</html>
+"#;
+
+/// The page, colouring readings by the same bands as the terminal.
+fn page(thresholds: Thresholds) -> String {
+    PAGE.replace("IN_TUNE", &thresholds.in_tune.to_string())
+        .replace("CLOSE", &thresholds.close.to_string())
+}
+
+/// A small HTTP server that serves a tuner page and streams readings to it
+/// as Server-Sent Events from `/events`.
+pub struct WebServer {
+    addr: SocketAddr,
+    /// A queue of events for each client streaming them, drained by its thread.
+    clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>
+}
+
+impl WebServer {
+
+    /// Start accepting connections on a background thread, each handled on a
+    /// thread of its own so a slow client doesn't hold up the others.
+    pub fn bind<A: ToSocketAddrs>(addr: A, thresholds: Thresholds) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&clients);
+        let page: Arc<str> = Arc::from(page(thresholds));
+        let connections = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // Past the limit new connections are closed straight away.
+                if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let (page, clients, connections) = (Arc::clone(&page), Arc::clone(&accepted), Arc::clone(&connections));
+                thread::spawn(move || {
+                    // A misbehaving client only costs itself its connection.
+                    let _ = handle(stream, &page, &clients);
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        Ok(WebServer { addr, clients })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Queue an event for every connected client, forgetting those that went
+    /// away or have fallen too far behind to catch up.
+    pub fn broadcast(&self, data: &str) {
+        let event: Arc<str> = Arc::from(format!("data: {}\n\n", data));
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.retain(|c| c.try_send(Arc::clone(&event)).is_ok());
+    }
+}
+
+impl Sink for WebServer {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        self.broadcast(&frame.reading.to_json(frame.midi_note, frame.cents));
+        Ok(())
+    }
+}
+
+fn handle(mut stream: TcpStream, page: &str, clients: &Mutex<Vec<SyncSender<Arc<str>>>>) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    // Nothing past the first few kilobytes is read.
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST as u64);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    if !request.ends_with('\n') {
+        return stream.write_all(b"HTTP/1.1 414 URI Too Long\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    }
+    // Skip the headers, nothing in them changes the response.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/")) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+            )
+        }
+        (Some("GET"), Some("/events")) => {
+            // Registered first so events broadcast once the client has the
+            // headers are queued for it, and sent after them.
+            let (sender, events) = mpsc::sync_channel(EVENT_QUEUE);
+            clients.lock().unwrap_or_else(|e| e.into_inner()).push(sender);
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n")?;
+            stream.flush()?;
+            // Until the client goes away or is dropped for falling behind.
+            for event in events {
+                stream.write_all(event.as_bytes())?;
+                stream.flush()?;
+            }
+            Ok(())
+        }
+        _ => stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    }
+}
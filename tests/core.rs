@@ -0,0 +1,35 @@
+//! What's left with `--no-default-features`, the detection core on its own.
+use std::f32::consts::PI;
+use clituner::bac::bitstream::estimate_with;
+use clituner::bac::conf::Window;
+use clituner::bac::decl::Declarative;
+use clituner::bac::imp::Imperative;
+use clituner::note::{freq_to_note, parse_note};
+use clituner::preset::{Preset, PRESETS};
+
+fn sine(freq: f32) -> [f32; 2048] {
+    let mut signal = [0.0; 2048];
+    for (n, s) in signal.iter_mut().enumerate() {
+        *s = (2.0 * PI * freq * n as f32 / 44100.0).sin();
+    }
+    signal
+}
+
+#[test]
+fn test_estimate_on_the_stack() {
+    let window = Window::default();
+    let (mut bits, mut corr) = ([0u32; 64], [0u32; 1024]);
+    let (freq, confidence) = estimate_with::<Imperative>(&window, &sine(110.0), &mut bits, &mut corr).unwrap();
+    assert!((freq - 110.0).abs() < 0.1, "got {} Hz", freq);
+    assert!(confidence > 0.9);
+    assert_eq!(estimate_with::<Declarative>(&window, &sine(110.0), &mut bits, &mut corr), Some((freq, confidence)));
+    assert_eq!(estimate_with::<Imperative>(&window, &[0.0; 2048], &mut bits, &mut corr), None);
+}
+
+#[test]
+fn test_notes() {
+    assert_eq!(freq_to_note(110.0, 440.0), (45, 0));
+    assert_eq!(parse_note("A2"), Some(45));
+    let guitar = &PRESETS[Preset::find("guitar").unwrap()];
+    assert_eq!(guitar.target(111.0, 440.0), (45, 16));
+}
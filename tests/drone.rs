@@ -0,0 +1,74 @@
+use clituner::drone::{Drone, DroneFilter, Notch};
+use clituner::note::midi_to_freq;
+use clituner::preset::{Preset, PRESETS};
+use clituner::reading::rms;
+use clituner::tone::{Tone, Waveform};
+
+fn violin() -> &'static Preset {
+    &PRESETS[Preset::find("violin").unwrap()]
+}
+
+#[test]
+fn test_drone_cycles() {
+    let mut drone = Drone::new(violin(), 440.0, 2.0);
+    assert_eq!(drone.update(10.0), Some(midi_to_freq(55, 440.0)));
+    assert_eq!(drone.update(11.9), Some(midi_to_freq(55, 440.0)));
+    drone.update(12.0);
+    assert_eq!(drone.note(), Some(62));
+    drone.update(14.0);
+    drone.update(16.0);
+    drone.update(18.0);
+    assert_eq!(drone.note(), Some(55));
+}
+
+#[test]
+fn test_drone_hold_and_skip() {
+    let mut drone = Drone::new(violin(), 440.0, 2.0);
+    drone.update(0.0);
+    drone.toggle_hold();
+    drone.update(1.0);
+    drone.update(100.0);
+    assert_eq!(drone.note(), Some(55));
+
+    drone.next();
+    drone.update(101.0);
+    assert_eq!(drone.update(200.0), Some(midi_to_freq(62, 440.0)));
+
+    drone.set_preset(&PRESETS[0]);
+    assert_eq!(drone.update(201.0), None);
+}
+
+#[test]
+fn test_drone_never_moves_with_zero_step() {
+    let mut drone = Drone::new(violin(), 440.0, 0.0);
+    drone.update(0.0);
+    drone.update(1000.0);
+    assert_eq!(drone.note(), Some(55));
+}
+
+fn filtered_rms(filter: &mut DroneFilter, frequency: f32) -> f32 {
+    let mut tone = Tone::new(Waveform::Sine, frequency, 44100, 1.0);
+    let samples: Vec<f32> = (0..44100).map(|_| filter.process(tone.next_sample())).collect();
+    // Skip the first half while the filter settles.
+    rms(&samples[22050..])
+}
+
+#[test]
+fn test_filter_notches_drone_harmonics() {
+    let mut filter = DroneFilter::new(44100);
+    filter.set_frequency(Some(220.0));
+    assert!(filtered_rms(&mut filter, 220.0) < 0.01);
+    assert!(filtered_rms(&mut filter, 440.0) < 0.01);
+    assert!(filtered_rms(&mut filter, 293.66) > 0.6);
+
+    filter.set_frequency(None);
+    assert!(filtered_rms(&mut filter, 220.0) > 0.7);
+}
+
+#[test]
+fn test_notch_passes_through_without_frequency() {
+    let mut notch = Notch::new(44100);
+    assert_eq!(notch.process(0.5), 0.5);
+    notch.set_frequency(Some(30000.0));
+    assert_eq!(notch.process(-0.25), -0.25);
+}
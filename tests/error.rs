@@ -0,0 +1,26 @@
+use std::error::Error as _;
+use std::io;
+use clituner::error::{Error, EXIT_AUDIO, EXIT_USAGE};
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(Error::Parse(String::from("bad")).exit_code(), EXIT_USAGE);
+    assert_eq!(Error::NoDevice("input").exit_code(), EXIT_AUDIO);
+    assert_eq!(Error::SampleRate(800).exit_code(), EXIT_AUDIO);
+    assert_eq!(Error::Stream(cpal::StreamError::DeviceNotAvailable).exit_code(), EXIT_AUDIO);
+    let missing = io::Error::new(io::ErrorKind::NotFound, "gone");
+    assert_eq!(Error::file("out.csv", missing).exit_code(), 1);
+}
+
+#[test]
+fn test_messages() {
+    assert_eq!(
+        Error::NoDevice("input").to_string(),
+        "no audio input device found, is one plugged in and enabled?"
+    );
+    let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+    let err = Error::io("could not serve on 0.0.0.0:80", denied);
+    assert_eq!(err.to_string(), "could not serve on 0.0.0.0:80: denied");
+    assert!(err.source().is_some());
+    assert!(Error::Parse(String::from("bad")).source().is_none());
+}
@@ -0,0 +1,62 @@
+use std::f32::consts::PI;
+use proptest::prelude::*;
+use clituner::bac::bitstream::estimate_with;
+use clituner::bac::conf::Window;
+use clituner::bac::imp::{Bitstream, Imperative};
+
+fn sine(freq: f32, gain: f32, len: usize) -> Vec<f32> {
+    (0..len).map(|x| (gain * (2.0 * PI * freq * x as f32 / 44100.0).sin()).clamp(-1.0, 1.0)).collect()
+}
+
+#[test]
+fn test_silence_and_dc() {
+    let size = Window::default().buff_size;
+    assert_eq!(Bitstream::estimate_pitch(&vec![0.0; size]), None);
+    assert_eq!(Bitstream::estimate_pitch(&vec![0.5; size]), None);
+    assert_eq!(Bitstream::estimate_pitch(&vec![-0.5; size]), None);
+    assert_eq!(Bitstream::estimate_pitch(&[]), None);
+}
+
+#[test]
+fn test_clipped() {
+    for &freq in &[82.41, 220.0, 440.0] {
+        let estimate = Bitstream::estimate_pitch(&sine(freq, 20.0, 2048)).unwrap();
+        assert!((estimate / freq - 1.0).abs() < 0.001, "{} estimated as {}", freq, estimate);
+    }
+}
+
+#[test]
+fn test_short_signal() {
+    let signal = sine(220.0, 1.0, 2048);
+    for len in (0..signal.len()).step_by(13) {
+        if let Some(freq) = Bitstream::estimate_pitch(&signal[..len]) {
+            assert!(freq.is_finite() && freq > 0.0);
+        }
+    }
+}
+
+fn signals() -> impl Strategy<Value = Vec<f32>> {
+    let samples = prop_oneof![
+        -1.0f32..1.0,
+        Just(0.0f32),
+        Just(1.0f32),
+        Just(-1.0f32),
+        any::<f32>()
+    ];
+    prop::collection::vec(samples, 0..2304)
+}
+
+proptest! {
+    #[test]
+    fn estimates_are_finite(signal in signals()) {
+        let estimate = Bitstream::estimate_pitch_with_confidence(&signal);
+        if let Some((freq, confidence)) = estimate {
+            prop_assert!(freq.is_finite() && freq > 0.0);
+            prop_assert!((0.0..=1.0).contains(&confidence));
+        }
+        // The same without allocating.
+        let (mut bits, mut corr) = ([0u32; 64], [0u32; 1024]);
+        let unallocated = estimate_with::<Imperative>(&Window::default(), &signal, &mut bits, &mut corr);
+        prop_assert_eq!(format!("{:?}", unallocated), format!("{:?}", estimate));
+    }
+}
@@ -0,0 +1,28 @@
+use clituner::history::{History, LABEL_WIDTH};
+
+#[test]
+fn test_push_drops_old_readings() {
+    let mut history = History::new(2.0);
+    history.push(0.0, 440.0);
+    history.push(1.0, 440.0);
+    history.push(2.5, 440.0);
+    assert_eq!(history.len(), 2);
+}
+
+#[test]
+fn test_render_in_tune_is_centred() {
+    let mut history = History::new(1.0);
+    history.push(1.0, 440.0);
+    let lines = history.render(1.0, 440.0, LABEL_WIDTH + 2, 2);
+    assert_eq!(lines, vec![" +50  ", "   0 \u{2808}"]);
+}
+
+#[test]
+fn test_render_clamps_and_scrolls() {
+    let mut history = History::new(1.0);
+    history.push(0.0, 500.0);
+    history.push(1.0, 400.0);
+    let lines = history.render(1.0, 440.0, LABEL_WIDTH + 2, 1);
+    // Oldest reading far sharp at the top left, newest far flat at the bottom right.
+    assert_eq!(lines, vec![" +50\u{2801}\u{2880}"]);
+}
@@ -0,0 +1,51 @@
+use clituner::intonation::{Check, Intonation, Prompt};
+use clituner::reading::Reading;
+
+fn reading(timestamp: f64, frequency: f32) -> Reading {
+    Reading { timestamp, frequency, confidence: 0.9, rms: 0.1 }
+}
+
+/// Hold `frequency` for a second, returning the check if one came out.
+fn hold(intonation: &mut Intonation, start: f64, frequency: f32) -> Option<Check> {
+    (0..10).filter_map(|i| intonation.push(reading(start + i as f64 * 0.1, frequency))).last()
+}
+
+#[test]
+fn test_walks_through_strings() {
+    let mut intonation = Intonation::new(&[40, 45], 440.0);
+    assert_eq!(intonation.prompt(), Prompt::Open(40));
+    // Too far from E2 to be the open string.
+    assert_eq!(hold(&mut intonation, 0.0, 110.0), None);
+    assert_eq!(intonation.prompt(), Prompt::Open(40));
+
+    assert_eq!(hold(&mut intonation, 1.0, 82.0), None);
+    assert_eq!(intonation.prompt(), Prompt::Fretted(40));
+    // Still ringing open doesn't count as the fretted note.
+    assert_eq!(hold(&mut intonation, 2.0, 82.0), None);
+
+    let check = hold(&mut intonation, 3.0, 165.0).unwrap();
+    assert_eq!(check.cents(), 11);
+    assert_eq!(check.advice(), "sharp, move the saddle back");
+    assert_eq!(intonation.prompt(), Prompt::Open(45));
+
+    hold(&mut intonation, 4.0, 110.0);
+    let check = hold(&mut intonation, 5.0, 219.9).unwrap();
+    assert_eq!(check.summary(), "A2 12th fret -1¢ in tune, leave the saddle");
+    assert_eq!(intonation.prompt(), Prompt::Done);
+    assert_eq!(intonation.checks().len(), 2);
+}
+
+#[test]
+fn test_waits_for_steady_note() {
+    let mut intonation = Intonation::new(&[45], 440.0);
+    for i in 0..20 {
+        let wobble = if i % 2 == 0 { 108.0 } else { 112.0 };
+        intonation.push(reading(i as f64 * 0.1, wobble));
+    }
+    assert_eq!(intonation.prompt(), Prompt::Open(45));
+    hold(&mut intonation, 2.0, 110.0);
+    assert_eq!(intonation.prompt(), Prompt::Fretted(45));
+
+    let check = Check { note: 45, open: 110.0, fretted: 218.0 };
+    assert_eq!(check.advice(), "flat, move the saddle forward");
+}
@@ -0,0 +1,42 @@
+use proptest::prelude::*;
+use clituner::bac::bitstream::Kernel;
+use clituner::bac::conf::Window;
+use clituner::bac::decl::Declarative;
+use clituner::bac::imp::Imperative;
+use clituner::bac::wide::{Simd, Wide};
+
+fn correlate<K: Kernel>(bits: &[u32], window: &Window, start_pos: usize) -> Vec<u32> {
+    let mut corr = vec![0; window.mid_pos];
+    K::default().correlate(bits, window.mid_array, start_pos, &mut corr);
+    corr
+}
+
+fn windows() -> impl Strategy<Value = Window> {
+    prop_oneof![
+        Just(Window::default()),
+        Just(Window::new(44100, 25, 250).unwrap()),
+        Just(Window::new(44100, 200, 2000).unwrap()),
+        Just(Window::new(8000, 100, 1000).unwrap())
+    ]
+}
+
+fn bitstreams() -> impl Strategy<Value = (Window, Vec<u32>, usize)> {
+    windows().prop_flat_map(|window| {
+        (Just(window), prop::collection::vec(any::<u32>(), window.array_size), 0..window.mid_pos)
+    })
+}
+
+proptest! {
+    #[test]
+    fn kernels_agree((window, bits, start_pos) in bitstreams()) {
+        let expected = correlate::<Imperative>(&bits, &window, start_pos);
+        prop_assert_eq!(&expected, &correlate::<Declarative>(&bits, &window, start_pos));
+        prop_assert_eq!(&expected, &correlate::<Wide>(&bits, &window, start_pos));
+        prop_assert_eq!(&expected, &correlate::<Simd>(&bits, &window, start_pos));
+    }
+
+    #[test]
+    fn matching_itself_costs_nothing((window, bits, _) in bitstreams()) {
+        prop_assert_eq!(correlate::<Imperative>(&bits, &window, 0)[0], 0);
+    }
+}
@@ -0,0 +1,76 @@
+use clituner::midi::{MidiMessage, MidiOut, PitchToMidi, SmfWriter};
+use clituner::reading::{Frame, Reading};
+
+fn frame(timestamp: f64, midi_note: i32, cents: i32) -> Frame {
+    let reading = Reading { timestamp, frequency: 0.0, confidence: 1.0, rms: 1.0 };
+    Frame { reading, midi_note, cents }
+}
+
+#[test]
+fn test_message_bytes() {
+    assert_eq!(MidiMessage::NoteOn { note: 60, velocity: 100 }.to_bytes(1), vec![0x91, 60, 100]);
+    assert_eq!(MidiMessage::NoteOff { note: 60 }.to_bytes(0), vec![0x80, 60, 0]);
+    assert_eq!(MidiMessage::PitchBend(0).to_bytes(0), vec![0xE0, 0x00, 0x40]);
+    assert_eq!(MidiMessage::PitchBend(-8192).to_bytes(0), vec![0xE0, 0x00, 0x00]);
+    assert_eq!(MidiMessage::PitchBend(8191).to_bytes(0), vec![0xE0, 0x7F, 0x7F]);
+}
+
+#[test]
+fn test_note_changes_and_bend() {
+    let mut midi = PitchToMidi::new(2);
+    assert_eq!(midi.frame(&frame(0.0, 60, 0)), vec![
+        MidiMessage::PitchBend(0),
+        MidiMessage::NoteOn { note: 60, velocity: 127 }
+    ]);
+    assert_eq!(midi.frame(&frame(0.1, 60, 0)), vec![]);
+    assert_eq!(midi.frame(&frame(0.2, 60, 50)), vec![MidiMessage::PitchBend(2048)]);
+    // Just past the half-way point stays on the same note.
+    assert_eq!(midi.frame(&frame(0.3, 61, -45)), vec![MidiMessage::PitchBend(2252)]);
+    assert_eq!(midi.frame(&frame(0.4, 62, 0)), vec![
+        MidiMessage::NoteOff { note: 60 },
+        MidiMessage::PitchBend(0),
+        MidiMessage::NoteOn { note: 62, velocity: 127 }
+    ]);
+}
+
+#[test]
+fn test_release_after_silence() {
+    let mut midi = PitchToMidi::new(2);
+    midi.frame(&frame(1.0, 45, 0));
+    assert_eq!(midi.tick(1.1), vec![]);
+    assert_eq!(midi.tick(2.0), vec![MidiMessage::NoteOff { note: 45 }]);
+    assert_eq!(midi.tick(3.0), vec![]);
+}
+
+#[test]
+fn test_smf_bytes() {
+    let path = std::env::temp_dir().join(format!("clituner-{}.mid", std::process::id()));
+    let mut smf = SmfWriter::create(&path, 0).unwrap();
+    smf.send(10.0, MidiMessage::NoteOn { note: 60, velocity: 64 }).unwrap();
+    smf.send(10.5, MidiMessage::NoteOff { note: 60 }).unwrap();
+    // Complete before `finish`, as when the tuner is interrupted.
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[..14], b"MThd\0\0\0\x06\0\0\0\x01\x01\xE0");
+    assert_eq!(&bytes[14..22], b"MTrk\0\0\0\x14");
+    assert_eq!(&bytes[22..], &[
+        0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+        0x00, 0x90, 60, 64,
+        // Half a second is 480 ticks.
+        0x83, 0x60, 0x80, 60, 0,
+        0x00, 0xFF, 0x2F, 0x00
+    ][..]);
+
+    smf.finish().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_empty_smf() {
+    let path = std::env::temp_dir().join(format!("clituner-empty-{}.mid", std::process::id()));
+    SmfWriter::create(&path, 0).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[14..22], b"MTrk\0\0\0\x0b");
+    assert_eq!(bytes.len(), 22 + 11);
+    std::fs::remove_file(&path).unwrap();
+}
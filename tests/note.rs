@@ -0,0 +1,39 @@
+use clituner::note::{freq_to_note, midi_to_freq, note_name, parse_note};
+use clituner::preset::{Preset, PRESETS};
+
+#[test]
+fn test_freq_to_note() {
+    assert_eq!(freq_to_note(440.0, 440.0), (69, 0));
+    assert_eq!(freq_to_note(82.0, 440.0), (40, -9));
+    assert_eq!(freq_to_note(444.0, 444.0), (69, 0));
+    assert_eq!(format!("{:.2}", midi_to_freq(60, 440.0)), "261.63");
+}
+
+#[test]
+fn test_note_name() {
+    assert_eq!(note_name(40), "E2");
+    assert_eq!(note_name(61), "C#4");
+    assert_eq!(note_name(0), "C-1");
+}
+
+#[test]
+fn test_preset_target() {
+    let guitar = &PRESETS[Preset::find("Guitar").unwrap()];
+    // Closer to F2 than to E2 but still tuned against the low E string.
+    assert_eq!(guitar.target(86.0, 440.0).0, 40);
+    assert_eq!(guitar.target(110.0, 440.0), (45, 0));
+    assert_eq!(PRESETS[0].target(86.0, 440.0).0, 41);
+    assert_eq!(Preset::find("banjo"), None);
+}
+
+#[test]
+fn test_parse_note() {
+    assert_eq!(parse_note("A4"), Some(69));
+    assert_eq!(parse_note("e2"), Some(40));
+    assert_eq!(parse_note("C#4"), Some(61));
+    assert_eq!(parse_note("Bb2"), Some(46));
+    assert_eq!(parse_note("C-1"), Some(0));
+    assert_eq!(parse_note("H2"), None);
+    assert_eq!(parse_note("A"), None);
+    assert_eq!(parse_note("A9"), None);
+}
@@ -0,0 +1,32 @@
+use std::net::UdpSocket;
+use std::time::Duration;
+use clituner::osc::{frame_messages, OscArg, OscMessage, OscSender};
+use clituner::reading::{Frame, Reading};
+use clituner::sink::Sink;
+
+#[test]
+fn test_message_bytes() {
+    let message = OscMessage::new("/tuner/pitch", vec![OscArg::Float(440.0)]);
+    assert_eq!(message.to_bytes(), b"/tuner/pitch\0\0\0\0,f\0\0\x43\xdc\0\0".to_vec());
+
+    let message = OscMessage::new("/a", vec![OscArg::Int(-2), OscArg::Str(String::from("abcd"))]);
+    assert_eq!(message.to_bytes(), b"/a\0\0,is\0\xff\xff\xff\xfeabcd\0\0\0\0".to_vec());
+}
+
+#[test]
+fn test_send_to_listener() {
+    let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+    listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut sender = OscSender::new(listener.local_addr().unwrap()).unwrap();
+
+    let reading = Reading { timestamp: 0.0, frequency: 110.0, confidence: 0.5, rms: 0.1 };
+    let frame = Frame { reading, midi_note: 45, cents: -3 };
+    sender.write(&frame).unwrap();
+
+    let mut buf = [0; 256];
+    for expected in frame_messages(&frame) {
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], &expected.to_bytes()[..]);
+    }
+    assert_eq!(frame_messages(&frame)[1], OscMessage::new("/tuner/note", vec![OscArg::Int(45)]));
+}
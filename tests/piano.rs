@@ -0,0 +1,86 @@
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use clituner::bac::conf::CONFIG;
+use clituner::note::midi_to_freq;
+use clituner::piano::{measure_inharmonicity, railsback, typical_inharmonicity, Measurer, Stretch};
+
+const SAMPLE_RATE: f32 = 44100.0;
+
+/// A string with inharmonicity `b`, partials falling off as 1/k.
+fn string(f0: f32, b: f32) -> Vec<f32> {
+    (0..CONFIG.buff_size).map(|n| {
+        let t = n as f32 / SAMPLE_RATE;
+        (1..=12).map(|k| {
+            let k = k as f32;
+            let partial = k * f0 * (1.0 + b * k * k).sqrt();
+            if partial < SAMPLE_RATE / 2.0 { (2.0 * PI * partial * t + k).sin() / k } else { 0.0 }
+        }).sum::<f32>() * 0.2
+    }).collect()
+}
+
+#[test]
+fn test_measure_inharmonicity() {
+    for &(f0, b) in [(130.81f32, 0.0004f32), (261.63, 0.001), (98.0, 0.0002)].iter() {
+        let sounding = f0 * (1.0 + b).sqrt();
+        let measured = measure_inharmonicity(&string(f0, b), SAMPLE_RATE, sounding).unwrap();
+        assert!((measured - b).abs() < b * 0.25, "measured {} for B {} at {} Hz", measured, b, f0);
+    }
+    let harmonic = measure_inharmonicity(&string(196.0, 0.0), SAMPLE_RATE, 196.0).unwrap();
+    assert!(harmonic < 2e-5);
+}
+
+#[test]
+fn test_measurer() {
+    let stretch = Arc::new(Mutex::new(Stretch::new(1.0, 440.0)));
+    let measurer = Measurer::new(Arc::clone(&stretch));
+    let (f0, b) = (261.63f32, 0.001f32);
+    measurer.measure(&string(f0, b), SAMPLE_RATE, f0 * (1.0 + b).sqrt());
+    for _ in 0..100 {
+        if stretch.lock().unwrap().measured() > 0 {
+            break
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let stretch = stretch.lock().unwrap();
+    assert_eq!(stretch.measured(), 1);
+    assert!((stretch.inharmonicity(60) - b).abs() < b * 0.25);
+}
+
+#[test]
+fn test_railsback_targets() {
+    assert_eq!(railsback(69), 0.0);
+    assert!(railsback(21) < -20.0 && railsback(108) > 20.0);
+
+    let stretch = Stretch::new(1.0, 440.0);
+    assert_eq!(stretch.target(440.0), (69, 0));
+    // C8 in equal temperament is flat of its stretched target.
+    let (key, cents) = stretch.target(midi_to_freq(108, 440.0));
+    assert_eq!(key, 108);
+    assert_eq!(cents, -railsback(108).round() as i32);
+
+    let flat = Stretch::new(0.0, 440.0);
+    assert_eq!(flat.target(midi_to_freq(108, 440.0)), (108, 0));
+}
+
+#[test]
+fn test_measured_stretch() {
+    let mut stretch = Stretch::new(1.0, 440.0);
+    stretch.record(440.0, typical_inharmonicity(69) * 2.0);
+    stretch.record(440.0, typical_inharmonicity(69) * 4.0);
+    assert_eq!(stretch.measured(), 1);
+    assert!((stretch.inharmonicity(69) / typical_inharmonicity(69) - 3.0).abs() < 1e-3);
+    // With one key measured the whole curve is scaled to match it.
+    assert!((stretch.inharmonicity(90) / typical_inharmonicity(90) - 3.0).abs() < 1e-3);
+
+    stretch.record(midi_to_freq(81, 440.0), typical_inharmonicity(81));
+    let between = stretch.inharmonicity(75) / typical_inharmonicity(75);
+    assert!(between > 1.0 && between < 3.0);
+
+    assert_eq!(stretch.offset(69), 0.0);
+    assert!(stretch.offset(81) > 0.0 && stretch.offset(57) < 0.0);
+    assert!(stretch.offset(108) > stretch.offset(96));
+    // Far more inharmonic than a plain equal tempered octave.
+    assert!(stretch.offset(81) > 1.0);
+}
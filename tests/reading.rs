@@ -0,0 +1,17 @@
+use clituner::reading::{rms, Reading};
+
+#[test]
+fn test_rms() {
+    assert_eq!(rms(&[]), 0.0);
+    assert_eq!(rms(&[0.5, -0.5, 0.5, -0.5]), 0.5);
+}
+
+#[test]
+fn test_to_json() {
+    let reading = Reading { timestamp: 1600000000.25, frequency: 82.02, confidence: 0.9, rms: 0.125 };
+    assert_eq!(
+        reading.to_json(40, -9),
+        "{\"timestamp\":1600000000.250,\"frequency\":82.020,\"confidence\":0.900,\
+         \"note\":\"E\",\"octave\":2,\"midi\":40,\"cents\":-9,\"rms\":0.12500}"
+    );
+}
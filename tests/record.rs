@@ -0,0 +1,141 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use clituner::reading::{Frame, Reading};
+use clituner::record::{rotated_path, CsvRecorder, CSV_HEADER};
+use clituner::sink::{Sink, Tap};
+
+fn frame(frequency: f32) -> Frame {
+    let reading = Reading { timestamp: 10.5, frequency, confidence: 0.75, rms: 0.25 };
+    Frame { reading, midi_note: 45, cents: 3 }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("clituner-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path).unwrap().lines().map(String::from).collect()
+}
+
+#[test]
+fn test_rotated_path() {
+    assert_eq!(rotated_path(Path::new("a/session.csv"), 0), Path::new("a/session.csv"));
+    assert_eq!(rotated_path(Path::new("a/session.csv"), 2), Path::new("a/session.2.csv"));
+    assert_eq!(rotated_path(Path::new("session"), 1), Path::new("session.1"));
+}
+
+#[test]
+fn test_record_and_rotate() {
+    let dir = temp_dir("rotate");
+    let path = dir.join("session.csv");
+    let mut recorder = CsvRecorder::create(&path, false, Some(2)).unwrap();
+    for _ in 0..3 {
+        recorder.write(&frame(110.0)).unwrap();
+    }
+    recorder.finish().unwrap();
+    assert_eq!(recorder.current_path(), dir.join("session.1.csv"));
+
+    let first = lines(&path);
+    assert_eq!(first.len(), 3);
+    assert_eq!(first[0], CSV_HEADER);
+    assert_eq!(first[1], "10.500,110.000,A2,3,0.25000,0.750");
+    assert_eq!(lines(&dir.join("session.1.csv")).len(), 2);
+}
+
+#[test]
+fn test_record_append() {
+    let dir = temp_dir("append");
+    let path = dir.join("session.csv");
+    for _ in 0..2 {
+        let mut recorder = CsvRecorder::create(&path, true, None).unwrap();
+        recorder.write(&frame(110.0)).unwrap();
+        recorder.finish().unwrap();
+    }
+    let rows = lines(&path);
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows.iter().filter(|r| r.as_str() == CSV_HEADER).count(), 1);
+}
+
+#[test]
+fn test_append_and_rotate() {
+    let dir = temp_dir("append-rotate");
+    let path = dir.join("session.csv");
+    let mut recorder = CsvRecorder::create(&path, false, Some(2)).unwrap();
+    for _ in 0..3 {
+        recorder.write(&frame(110.0)).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    // The next session fills up session.1.csv and moves on to session.2.csv.
+    let mut recorder = CsvRecorder::create(&path, true, Some(2)).unwrap();
+    assert_eq!(recorder.current_path(), dir.join("session.1.csv"));
+    for _ in 0..2 {
+        recorder.write(&frame(110.0)).unwrap();
+    }
+    recorder.finish().unwrap();
+    assert_eq!(recorder.current_path(), dir.join("session.2.csv"));
+    assert_eq!(lines(&path).len(), 3);
+    assert_eq!(lines(&dir.join("session.1.csv")).len(), 3);
+    assert_eq!(lines(&dir.join("session.2.csv")).len(), 2);
+}
+
+struct Collect(Arc<Mutex<Vec<f32>>>);
+
+impl Sink for Collect {
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        self.0.lock().unwrap().push(frame.reading.frequency);
+        Ok(())
+    }
+}
+
+struct Broken;
+
+impl Sink for Broken {
+    fn write(&mut self, _: &Frame) -> io::Result<()> {
+        Err(io::Error::other("broken"))
+    }
+}
+
+#[test]
+fn test_tap_feeds_sinks() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let tap = Tap::new(vec![Box::new(Broken), Box::new(Collect(Arc::clone(&seen)))]);
+    tap.send(frame(100.0));
+    tap.send(frame(200.0));
+    let errors = tap.finish();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(*seen.lock().unwrap(), vec![100.0, 200.0]);
+}
+
+struct Ticks(Arc<Mutex<Vec<f64>>>);
+
+impl Sink for Ticks {
+    fn write(&mut self, _: &Frame) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn tick(&mut self, now: f64) -> io::Result<()> {
+        self.0.lock().unwrap().push(now);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_tap_ticks_on_the_readings_clock() {
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+    let tap = Tap::new(vec![Box::new(Ticks(Arc::clone(&ticks)))]);
+    // Readings 10.5 seconds after the epoch, nowhere near the system clock.
+    tap.send(frame(100.0));
+    thread::sleep(Duration::from_millis(200));
+    tap.finish();
+    let ticks = ticks.lock().unwrap();
+    assert!(!ticks.is_empty());
+    assert!(ticks.iter().all(|&t| t > 10.5 && t < 11.5), "{:?}", ticks);
+}
@@ -0,0 +1,82 @@
+use std::f32::consts::PI;
+use clituner::reading::Reading;
+use clituner::stability::Stability;
+
+/// Readings at the rate the tuner makes them, a hop of 256 samples at 44.1 kHz.
+const RATE: f32 = 44100.0 / 256.0;
+
+fn sing(stability: &mut Stability, start: f64, seconds: f32, pitch: impl Fn(f32) -> f32) {
+    sing_at(RATE, stability, start, seconds, pitch)
+}
+
+fn sing_at(rate: f32, stability: &mut Stability, start: f64, seconds: f32, pitch: impl Fn(f32) -> f32) {
+    for i in 0..(seconds * rate) as usize {
+        let t = i as f32 / rate;
+        stability.push(Reading { timestamp: start + t as f64, frequency: pitch(t), confidence: 0.9, rms: 0.1 });
+    }
+}
+
+fn cents(base: f32, cents: f32) -> f32 {
+    base * 2.0f32.powf(cents / 1200.0)
+}
+
+#[test]
+fn test_vibrato_and_drift() {
+    let mut stability = Stability::new();
+    sing(&mut stability, 0.0, 3.0, |t| cents(440.0, 20.0 * (2.0 * PI * 5.5 * t).sin() + 2.0 * t));
+    let analysis = stability.analysis().unwrap();
+    let vibrato = analysis.vibrato.unwrap();
+    assert!((vibrato.rate - 5.5).abs() < 0.3, "rate {}", vibrato.rate);
+    assert!((vibrato.extent - 20.0).abs() < 3.0, "extent {}", vibrato.extent);
+    assert!((analysis.drift - 2.0).abs() < 0.5, "drift {}", analysis.drift);
+    assert!((analysis.mean - cents(440.0, 3.0)).abs() < 0.3, "mean {}", analysis.mean);
+    assert!((analysis.deviation - 20.0 / 2.0f32.sqrt()).abs() < 2.0);
+}
+
+#[test]
+fn test_steady_note() {
+    let mut stability = Stability::new();
+    sing(&mut stability, 0.0, 0.5, |_| 220.0);
+    assert_eq!(stability.analysis(), None);
+    sing(&mut stability, 0.5, 1.5, |t| cents(220.0, (t * 37.0).sin()));
+    let analysis = stability.analysis().unwrap();
+    assert_eq!(analysis.vibrato, None);
+    assert!(analysis.deviation < 1.1);
+    assert!(analysis.to_json().ends_with("\"vibrato_rate\":null,\"vibrato_extent\":null}"));
+    assert!(analysis.summary().ends_with("no vibrato"));
+}
+
+#[test]
+fn test_new_note_resets() {
+    let mut stability = Stability::new();
+    sing(&mut stability, 0.0, 2.0, |_| 220.0);
+    sing(&mut stability, 2.0, 0.5, |_| 330.0);
+    assert_eq!(stability.analysis(), None);
+
+    sing(&mut stability, 2.5, 2.0, |_| 330.0);
+    assert!(stability.analysis().is_some());
+    // A pause ends the note too.
+    sing(&mut stability, 10.0, 0.5, |_| 330.0);
+    assert_eq!(stability.analysis(), None);
+}
+
+#[test]
+fn test_vibrato_below_half_the_reading_rate() {
+    // 10 readings a second alternating either side, which could be any odd
+    // multiple of 5 Hz.
+    let mut stability = Stability::new();
+    sing_at(10.0, &mut stability, 0.0, 3.0, |t| cents(440.0, if (t * 10.0).round() as i32 % 2 == 0 { 20.0 } else { -20.0 }));
+    assert_eq!(stability.analysis().unwrap().vibrato, None);
+
+    let mut stability = Stability::new();
+    sing(&mut stability, 0.0, 3.0, |t| cents(440.0, 20.0 * (2.0 * PI * 5.0 * t).sin()));
+    assert!((stability.analysis().unwrap().vibrato.unwrap().rate - 5.0).abs() < 0.3);
+}
+
+#[test]
+fn test_mean_forgets_readings_older_than_the_window() {
+    let mut stability = Stability::new();
+    sing(&mut stability, 0.0, 35.0, |t| if t < 4.0 { cents(440.0, 40.0) } else { 440.0 });
+    let analysis = stability.analysis().unwrap();
+    assert!((analysis.mean - 440.0).abs() < 0.01, "mean {}", analysis.mean);
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+use proptest::prelude::*;
+use clituner::bac::bitstream::Bitstream;
+use clituner::bac::conf::{Window, CONFIG};
+use clituner::bac::imp::{Imperative, ZeroCross};
+use clituner::bac::stream::StreamingBitstream;
+
+/// The correlation of the last window of `samples`, rebuilt from scratch with the
+/// zero crossing state carried over from what came before.
+fn rebuilt(window: Window, samples: &[f32]) -> Vec<u32> {
+    let mut zc = ZeroCross::new();
+    let bits: Vec<bool> = samples.iter().map(|&s| zc.run(s)).collect();
+    let mut bs = Bitstream::<Imperative>::with_window(window);
+    for (i, &bit) in bits[bits.len() - window.buff_size..].iter().enumerate() {
+        bs.set(i, bit);
+    }
+    bs.autocorrelate(window.min_period - 1);
+    bs.correlation().to_vec()
+}
+
+fn windows() -> impl Strategy<Value = Window> {
+    prop_oneof![
+        Just(Window::default()),
+        Just(Window::new(44100, 200, 2000).unwrap())
+    ]
+}
+
+proptest! {
+    #[test]
+    fn matches_rebuilding(
+        window in windows(),
+        samples in prop::collection::vec(-1.0f32..1.0, 4096..12000),
+        hops in prop::collection::vec(1usize..3000, 1..40)
+    ) {
+        let mut stream = StreamingBitstream::<Imperative>::with_window(window);
+        let mut pushed = 0;
+        for hop in hops.iter().cycle() {
+            if pushed >= samples.len() {
+                break
+            }
+            let end = (pushed + hop).min(samples.len());
+            stream.push(&samples[pushed..end]);
+            pushed = end;
+            if pushed >= window.buff_size {
+                prop_assert_eq!(stream.correlation(), &rebuilt(window, &samples[..pushed])[..]);
+                prop_assert_eq!(stream.samples(), &samples[pushed - window.buff_size..pushed]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pitch_of_a_stream() {
+    let period = CONFIG.samples_per_second as f32 / 261.626;
+    let signal: Vec<f32> = (0..8192).map(|x| {
+        let angle = x as f32 / period;
+        0.3 * (2.0 * PI * angle).sin() + 0.4 * (4.0 * PI * angle).sin() + 0.3 * (6.0 * PI * angle).sin()
+    }).collect();
+    let mut stream = StreamingBitstream::<Imperative>::new();
+    let estimates: Vec<_> = signal.chunks(256).filter_map(|hop| stream.push(hop)).collect();
+    // A reading for every hop once the first window is full, the same as estimating
+    // each window from scratch. The confidence can differ a little, the zero crossing
+    // state carrying over into the start of the window.
+    assert_eq!(estimates.len(), (8192 - CONFIG.buff_size) / 256 + 1);
+    for (k, (freq, confidence)) in estimates.into_iter().enumerate() {
+        let start = k * 256;
+        let window = &signal[start..start + CONFIG.buff_size];
+        let (expected_freq, expected_confidence) = Bitstream::<Imperative>::estimate_pitch_with_confidence(window).unwrap();
+        assert_eq!(freq, expected_freq);
+        assert!((confidence - expected_confidence).abs() < 0.05);
+    }
+    assert_eq!(format!("{:.3}", StreamingBitstream::<Imperative>::new().push(&signal[..CONFIG.buff_size]).unwrap().0), "261.626");
+}
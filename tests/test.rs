@@ -1,18 +1,25 @@
+#![allow(clippy::bool_assert_comparison, clippy::needless_range_loop)]
+
 use std::f32::consts::PI;
 use clituner::bac::imp;
 use clituner::bac::decl;
 use clituner::bac::conf;
+use clituner::bac::bitstream;
 
 const FREQ: f32 = 261.626;
-const PERIOD: f32 = conf::CONFIG.samples_per_second as f32 / FREQ;
+const HARMONICS: [f32; 3] = [0.3, 0.4, 0.3];
 
 fn generate_input() -> Vec<f32> {
-    (0..conf::CONFIG.buff_size).map(|x| {
-        let angle = x as f32 / PERIOD;
-        let first_harmonic = 0.3 * (2.0 * PI * angle).sin();
-        let second_harmonic = 0.4 * (4.0 * PI * angle).sin();
-        let third_harmonic = 0.3 * (6.0 * PI * angle).sin();
-        first_harmonic + second_harmonic + third_harmonic
+    partials(FREQ, &HARMONICS, 0.0, conf::CONFIG.buff_size)
+}
+
+/// `len` samples of a note at `freq` with partials of the given amplitudes,
+/// each starting `phase` radians into its cycle.
+fn partials(freq: f32, amplitudes: &[f32], phase: f32, len: usize) -> Vec<f32> {
+    let period = conf::CONFIG.samples_per_second as f32 / freq;
+    (0..len).map(|x| {
+        let angle = x as f32 / period;
+        amplitudes.iter().enumerate().map(|(h, a)| a * (2.0 * PI * (h + 1) as f32 * angle + phase).sin()).sum()
     }).collect()
 }
 
@@ -70,10 +77,10 @@ fn test_imperative_autocorrelate() {
     for i in 0..conf::CONFIG.buff_size {
         bs.set(i, zc.run(signal[i]));
     }
-    let (count, est_index, mut corr) = bs.autocorrelate(conf::CONFIG.min_period);
+    let (count, est_index) = bs.autocorrelate(conf::CONFIG.min_period);
     assert_eq!(count, 617);
     assert_eq!(est_index, 337);
-    let est_index = imp::Bitstream::handle_harmonics(count, est_index, &mut corr);
+    let est_index = bs.handle_harmonics(count, est_index, bs.correlation());
     assert_eq!(est_index, 168);
 }
 
@@ -85,10 +92,10 @@ fn test_declarative_autocorrelate() {
     for i in 0..conf::CONFIG.buff_size {
         bs.set(i, zc.run(signal[i]));
     }
-    let (count, est_index, mut corr) = bs.autocorrelate(conf::CONFIG.min_period);
+    let (count, est_index) = bs.autocorrelate(conf::CONFIG.min_period);
     assert_eq!(count, 617);
     assert_eq!(est_index, 337);
-    let est_index = decl::Bitstream::handle_harmonics(count, est_index, &mut corr);
+    let est_index = bs.handle_harmonics(count, est_index, bs.correlation());
     assert_eq!(est_index, 168);
 }
 
@@ -100,8 +107,8 @@ fn test_imperative_handle_harmonics() {
     for i in 0..conf::CONFIG.buff_size {
         bs.set(i, zc.run(signal[i]));
     }
-    let (count, est_index, mut corr) = bs.autocorrelate(conf::CONFIG.min_period);
-    let est_index = imp::Bitstream::handle_harmonics(count, est_index, &mut corr);
+    let (count, est_index) = bs.autocorrelate(conf::CONFIG.min_period);
+    let est_index = bs.handle_harmonics(count, est_index, bs.correlation());
     assert_eq!(est_index, 168);
 }
 
@@ -113,7 +120,98 @@ fn test_declarative_handle_harmonics() {
     for i in 0..conf::CONFIG.buff_size {
         bs.set(i, zc.run(signal[i]));
     }
-    let (count, est_index, mut corr) = bs.autocorrelate(conf::CONFIG.min_period);
-    let est_index = decl::Bitstream::handle_harmonics(count, est_index, &mut corr);
+    let (count, est_index) = bs.autocorrelate(conf::CONFIG.min_period);
+    let est_index = bs.handle_harmonics(count, est_index, bs.correlation());
     assert_eq!(est_index, 168);
 }
+
+#[test]
+fn test_estimate_pitch_with_confidence() {
+    let signal = generate_input();
+    let (imp_freq, imp_confidence) = imp::Bitstream::estimate_pitch_with_confidence(&signal).unwrap();
+    let (decl_freq, decl_confidence) = decl::Bitstream::estimate_pitch_with_confidence(&signal).unwrap();
+    assert_eq!(format!("{:.3}", imp_freq), "261.626");
+    assert_eq!(imp_freq, decl_freq);
+    assert_eq!(imp_confidence, decl_confidence);
+    assert!(imp_confidence > 0.9 && imp_confidence <= 1.0);
+}
+
+#[test]
+fn test_window() {
+    assert_eq!(conf::Window::new(44100, 50, 500), Some(conf::Window::default()));
+    assert_eq!(conf::Window::new(44100, 500, 50), None);
+    assert_eq!(conf::Window::new(44100, 0, 500), None);
+    assert_eq!(conf::Window::new(44100, 100, 30000), None);
+    assert_eq!(conf::Window::new(44100, 200, 2000).unwrap().buff_size, 512);
+    assert_eq!(conf::Window::new(44100, 25, 250).unwrap().buff_size, 4096);
+    assert_eq!(conf::Window::with_rate(44100), Some(conf::Window::default()));
+    assert_eq!(conf::Window::with_rate(48000).unwrap().buff_size, 2048);
+    assert_eq!(conf::Window::with_rate(800), None);
+}
+
+#[test]
+fn test_bass_and_treble_windows() {
+    let mut bass = imp::Bitstream::with_window(conf::Window::new(44100, 25, 250).unwrap());
+    let mut treble = decl::Bitstream::with_window(conf::Window::new(44100, 200, 2000).unwrap());
+    let (freq, _) = bass.estimate(&partials(30.87, &HARMONICS, 0.0, 4096)).unwrap();
+    assert_eq!(format!("{:.2}", freq), "30.87");
+    let (freq, _) = treble.estimate(&partials(880.0, &HARMONICS, 0.0, 512)).unwrap();
+    assert_eq!(format!("{:.0}", freq), "880");
+    // Neither is in range of the default window.
+    assert_ne!(format!("{:.2}", imp::Bitstream::estimate_pitch(&partials(30.87, &HARMONICS, 0.0, 4096)).unwrap()), "30.87");
+    assert_ne!(format!("{:.0}", imp::Bitstream::estimate_pitch(&partials(880.0, &HARMONICS, 0.0, 2048)).unwrap()), "880");
+}
+
+#[test]
+fn test_estimate_without_allocating() {
+    let signal = generate_input();
+    let mut bits = [0u32; 64];
+    let mut corr = [0u32; 1024];
+    let window = conf::Window::default();
+    let estimate = bitstream::estimate_with::<imp::Imperative>(&window, &signal, &mut bits, &mut corr);
+    assert_eq!(estimate, imp::Bitstream::estimate_pitch_with_confidence(&signal));
+    // Scratch space too small for the window.
+    assert_eq!(bitstream::estimate_with::<imp::Imperative>(&window, &signal, &mut bits[..32], &mut corr), None);
+}
+
+fn assert_fundamental(amplitudes: &[f32]) {
+    for &freq in &[82.41, 110.0, 146.83, 196.0, 246.94] {
+        for &phase in &[0.0, 1.0] {
+            let estimate = imp::Bitstream::estimate_pitch(&partials(freq, amplitudes, phase, conf::CONFIG.buff_size)).unwrap();
+            assert!((estimate / freq - 1.0).abs() < 0.01, "{} estimated as {}", freq, estimate);
+        }
+    }
+}
+
+#[test]
+fn test_second_harmonic_dominant() {
+    assert_fundamental(&[0.2, 1.0]);
+    assert_fundamental(&[0.3, 1.0, 0.5]);
+}
+
+#[test]
+fn test_third_harmonic_dominant() {
+    assert_fundamental(&[0.2, 0.0, 1.0]);
+    assert_fundamental(&[0.1, 0.4, 1.0]);
+}
+
+#[test]
+fn test_top_of_the_window() {
+    // B4 at A4 = 444 Hz, a period only just over `min_period`.
+    let estimate = imp::Bitstream::estimate_pitch(&partials(498.4, &[1.0], 0.0, conf::CONFIG.buff_size)).unwrap();
+    assert!((estimate / 498.4 - 1.0).abs() < 0.001, "estimated as {}", estimate);
+}
+
+#[test]
+fn test_handle_harmonics_bounds() {
+    let window = conf::Window::default();
+    let corr = vec![0u32; window.mid_pos];
+    // Outside the correlation, or too short to be a multiple of a period.
+    assert_eq!(bitstream::handle_harmonics(&window, 0, window.mid_pos, &corr), window.mid_pos);
+    assert_eq!(bitstream::handle_harmonics(&window, 0, usize::MAX, &corr), usize::MAX);
+    assert_eq!(bitstream::handle_harmonics(&window, 0, window.min_period, &corr), window.min_period);
+    assert_eq!(bitstream::handle_harmonics(&window, 0, 0, &[]), 0);
+    for est_index in 0..window.mid_pos {
+        assert!(bitstream::handle_harmonics(&window, 0, est_index, &corr) <= est_index);
+    }
+}
@@ -0,0 +1,49 @@
+use clituner::theme::{indicator, Band, ColorChoice, Palette, Thresholds};
+
+#[test]
+fn test_default_bands() {
+    let t = Thresholds::default();
+    assert_eq!(t.band(0), Band::InTune);
+    assert_eq!(t.band(-10), Band::InTune);
+    assert_eq!(t.band(10), Band::InTune);
+    assert_eq!(t.band(-11), Band::Close);
+    assert_eq!(t.band(30), Band::Close);
+    assert_eq!(t.band(-31), Band::Far);
+    assert_eq!(t.band(i32::MIN), Band::Far);
+}
+
+#[test]
+fn test_bands_are_symmetric() {
+    // The old colouring had -10 and -9 cents yellow but +9 and +10 green.
+    let t = Thresholds::default();
+    for cents in -100..=100 {
+        assert_eq!(t.band(cents), t.band(-cents), "at {} cents", cents);
+    }
+    assert_eq!(t.band(-9), Band::InTune);
+}
+
+#[test]
+fn test_custom_bands() {
+    let t = Thresholds { in_tune: 3, close: 15 };
+    assert_eq!(t.band(4), Band::Close);
+    assert_eq!(t.band(-16), Band::Far);
+}
+
+#[test]
+fn test_indicator() {
+    assert_eq!(indicator(Band::InTune, -5), "=");
+    assert_eq!(indicator(Band::Close, -20), "<");
+    assert_eq!(indicator(Band::Far, 40), ">>");
+}
+
+#[test]
+fn test_detect_palette() {
+    let term = Some("xterm-256color");
+    assert_eq!(Palette::detect(ColorChoice::Auto, None, term, true), Palette::Color);
+    assert_eq!(Palette::detect(ColorChoice::Auto, Some("1"), term, true), Palette::Mono);
+    assert_eq!(Palette::detect(ColorChoice::Auto, Some(""), term, true), Palette::Color);
+    assert_eq!(Palette::detect(ColorChoice::Auto, None, Some("dumb"), true), Palette::Plain);
+    assert_eq!(Palette::detect(ColorChoice::Auto, None, term, false), Palette::Plain);
+    assert_eq!(Palette::detect(ColorChoice::Always, Some("1"), None, false), Palette::Color);
+    assert_eq!(Palette::detect(ColorChoice::Never, None, term, true), Palette::Mono);
+}
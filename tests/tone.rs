@@ -0,0 +1,58 @@
+use clituner::note::cents_off;
+use clituner::tone::{Tone, Waveform};
+
+const WAVEFORMS: [Waveform; 4] = [Waveform::Sine, Waveform::Triangle, Waveform::Sawtooth, Waveform::Pluck];
+
+const SAMPLE_RATE: u32 = 44100;
+
+fn render(tone: &mut Tone, len: usize) -> Vec<f32> {
+    let mut samples = vec![0.0; len];
+    tone.fill(&mut samples, 1);
+    samples
+}
+
+/// Frequency of the strongest autocorrelation peak within a third of `expected`,
+/// interpolated between lags. The tuner's own detector is after other things.
+fn measure(samples: &[f32], expected: f32) -> f32 {
+    let period = SAMPLE_RATE as f32 / expected;
+    let window = samples.len() / 2;
+    let corr = |lag: usize| (0..window).map(|n| samples[n] * samples[n + lag]).sum::<f32>();
+    let lags = (period * 2.0 / 3.0) as usize..(period * 4.0 / 3.0) as usize;
+    let best = lags.max_by(|&a, &b| corr(a).partial_cmp(&corr(b)).unwrap()).unwrap();
+    let (before, at, after) = (corr(best - 1), corr(best), corr(best + 1));
+    let offset = 0.5 * (before - after) / (before - 2.0 * at + after);
+    SAMPLE_RATE as f32 / (best as f32 + offset)
+}
+
+#[test]
+fn test_tone_pitch() {
+    for &waveform in WAVEFORMS.iter() {
+        for &freq in [82.41, 220.0, 392.0, 1046.5].iter() {
+            let mut tone = Tone::new(waveform, freq, SAMPLE_RATE, 0.5);
+            let estimate = measure(&render(&mut tone, 16384), freq);
+            assert!(cents_off(estimate, freq).abs() <= 3, "{:?} at {} Hz came out at {} Hz", waveform, freq, estimate);
+        }
+    }
+}
+
+#[test]
+fn test_tone_volume() {
+    for &waveform in WAVEFORMS.iter() {
+        let mut tone = Tone::new(waveform, 440.0, 48000, 0.25);
+        let samples = render(&mut tone, 4096);
+        assert!(samples.iter().all(|s| s.abs() <= 0.25 + 1e-6), "{:?} is too loud", waveform);
+        assert!(samples.iter().any(|s| s.abs() > 0.1), "{:?} is too quiet", waveform);
+    }
+}
+
+#[test]
+fn test_fill_channels() {
+    let mut tone = Tone::new(Waveform::Sine, 440.0, 48000, 1.0);
+    let mut samples = [0.0; 8];
+    tone.fill(&mut samples, 2);
+    assert!(samples.chunks(2).all(|frame| frame[0] == frame[1]));
+    assert!(samples[2] > 0.0);
+
+    tone.set_frequency(220.0);
+    assert_eq!(tone.frequency(), 220.0);
+}
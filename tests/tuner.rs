@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use clituner::bac::conf::{Window, CONFIG};
+use clituner::Tuner;
+
+fn sine(freq: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    sine_at(CONFIG.samples_per_second, freq, amplitude, len)
+}
+
+fn sine_at(sample_rate: usize, freq: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    let period = sample_rate as f32 / freq;
+    (0..len).map(|n| amplitude * (2.0 * PI * n as f32 / period).sin()).collect()
+}
+
+#[test]
+fn test_readings_from_blocks() {
+    let mut tuner = Tuner::new();
+    let samples = sine(196.0, 0.5, 5000);
+    // Blocks that don't line up with the analysis buffer.
+    let readings: Vec<_> = samples.chunks(1000).flat_map(|block| tuner.push_samples(block)).collect();
+    let hop = tuner.hop();
+    assert_eq!(readings.len(), (5000 - CONFIG.buff_size) / hop + 1);
+    for reading in readings {
+        assert!((reading.frequency - 196.0).abs() < 1.0, "got {} Hz", reading.frequency);
+        assert!(reading.confidence > 0.5);
+        assert!((reading.rms - 0.5 / 2f32.sqrt()).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_gate() {
+    let gate = Arc::new(AtomicBool::new(true));
+    let mut tuner = Tuner::new().with_gate(Arc::clone(&gate));
+    let samples = sine(196.0, 0.5, CONFIG.buff_size);
+    assert!(tuner.push_samples(&vec![0.0; CONFIG.buff_size]).is_empty());
+
+    gate.store(false, Ordering::Relaxed);
+    // Only the last window is all tone.
+    assert!(!tuner.push_samples(&samples).is_empty());
+}
+
+#[test]
+fn test_gate_goes_by_level() {
+    // -63 dB.
+    assert!(Tuner::new().push_samples(&sine(196.0, 0.001, CONFIG.buff_size)).is_empty());
+    // -29 dB.
+    assert_eq!(Tuner::new().push_samples(&sine(196.0, 0.05, CONFIG.buff_size)).len(), 1);
+}
+
+#[test]
+fn test_window_sets_the_buffer_size() {
+    let window = Window::new(44100, 200, 2000).unwrap();
+    let mut tuner = Tuner::new().with_window(window).with_gate(Arc::new(AtomicBool::new(false)));
+    let readings = tuner.push_samples(&sine(880.0, 0.5, 2048));
+    assert_eq!(readings.len(), (2048 - window.buff_size) / tuner.hop() + 1);
+}
+
+#[test]
+fn test_hop() {
+    let mut tuner = Tuner::new().with_hop(CONFIG.buff_size).with_gate(Arc::new(AtomicBool::new(false)));
+    let samples = sine(196.0, 0.5, 5000);
+    let readings: Vec<_> = samples.chunks(300).flat_map(|block| tuner.push_samples(block)).collect();
+    assert_eq!(readings.len(), 5000 / CONFIG.buff_size);
+
+    // Overlapping windows read the same as separate ones.
+    let mut overlapping = Tuner::new().with_gate(Arc::new(AtomicBool::new(false)));
+    let every: Vec<_> = overlapping.push_samples(&samples);
+    assert_eq!(every.len(), (5000 - CONFIG.buff_size) / overlapping.hop() + 1);
+    assert!((every[0].frequency - readings[0].frequency).abs() < 0.01);
+    assert!((every[8].frequency - readings[1].frequency).abs() < 0.01);
+}
+
+#[test]
+fn test_sample_rate() {
+    let window = Window::with_rate(48000).unwrap();
+    let mut tuner = Tuner::new().with_window(window).with_gate(Arc::new(AtomicBool::new(false)));
+    let readings = tuner.push_samples(&sine_at(48000, 440.0, 0.5, window.buff_size));
+    assert_eq!(readings.len(), 1);
+    assert!((readings[0].frequency - 440.0).abs() < 0.5, "got {} Hz", readings[0].frequency);
+}
+
+#[test]
+fn test_readings_are_timed_by_their_samples() {
+    let mut tuner = Tuner::new();
+    let readings = tuner.push_samples(&sine(196.0, 0.5, 2 * CONFIG.buff_size));
+    let hop = tuner.hop() as f64 / CONFIG.samples_per_second as f64;
+    for pair in readings.windows(2) {
+        assert!((pair[1].timestamp - pair[0].timestamp - hop).abs() < 1e-6);
+    }
+    // The last is at the end of the samples, when they were pushed.
+    assert!((readings[readings.len() - 1].timestamp - clituner::reading::unix_time()).abs() < 0.5);
+}
@@ -0,0 +1,105 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use clituner::reading::{Frame, Reading};
+use clituner::sink::Sink;
+use clituner::theme::Thresholds;
+use clituner::web::WebServer;
+
+fn request(server: &WebServer, path: &str) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+    BufReader::new(stream)
+}
+
+/// The status line, with the headers read and thrown away.
+fn status(reader: &mut BufReader<TcpStream>) -> String {
+    let mut status = String::new();
+    reader.read_line(&mut status).unwrap();
+    let mut line = String::new();
+    while reader.read_line(&mut line).unwrap() > 2 {
+        line.clear();
+    }
+    status
+}
+
+#[test]
+fn test_serves_page() {
+    let server = WebServer::bind("127.0.0.1:0", Thresholds { in_tune: 5, close: 20 }).unwrap();
+    let mut reader = request(&server, "/");
+    assert!(status(&mut reader).starts_with("HTTP/1.1 200"));
+    let mut body = String::new();
+    reader.read_to_string(&mut body).unwrap();
+    assert!(body.contains("EventSource(\"/events\")"));
+    assert!(body.contains("off <= 5 ? \"in-tune\" : off <= 20 ? \"close\""));
+
+    let mut reader = request(&server, "/missing");
+    assert!(status(&mut reader).starts_with("HTTP/1.1 404"));
+}
+
+#[test]
+fn test_streams_readings() {
+    let mut server = WebServer::bind("127.0.0.1:0", Thresholds::default()).unwrap();
+    let mut reader = request(&server, "/events");
+    assert!(status(&mut reader).starts_with("HTTP/1.1 200"));
+
+    let reading = Reading { timestamp: 1.0, frequency: 440.0, confidence: 0.9, rms: 0.1 };
+    let frame = Frame { reading, midi_note: 69, cents: 2 };
+    server.write(&frame).unwrap();
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line.trim_end(), format!("data: {}", reading.to_json(69, 2)));
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, "\n");
+}
+
+#[test]
+fn test_silent_client_does_not_hold_up_others() {
+    let server = WebServer::bind("127.0.0.1:0", Thresholds::default()).unwrap();
+    // Connects and never sends a request.
+    let _silent = TcpStream::connect(server.local_addr()).unwrap();
+    let start = Instant::now();
+    let mut reader = request(&server, "/");
+    assert!(status(&mut reader).starts_with("HTTP/1.1 200"));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn test_stalled_client_does_not_hold_up_broadcasts() {
+    let server = WebServer::bind("127.0.0.1:0", Thresholds::default()).unwrap();
+    // Takes the stream and never reads it.
+    let mut stalled = request(&server, "/events");
+    assert!(status(&mut stalled).starts_with("HTTP/1.1 200"));
+    let mut reader = request(&server, "/events");
+    assert!(status(&mut reader).starts_with("HTTP/1.1 200"));
+
+    let data = "x".repeat(10_000);
+    let start = Instant::now();
+    for _ in 0..200 {
+        server.broadcast(&data);
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line.trim_end(), format!("data: {}", data));
+}
+
+#[test]
+fn test_long_request_line() {
+    let server = WebServer::bind("127.0.0.1:0", Thresholds::default()).unwrap();
+    let mut reader = request(&server, &format!("/{}", "a".repeat(10_000)));
+    assert!(status(&mut reader).starts_with("HTTP/1.1 414"));
+}
+
+#[test]
+fn test_connections_are_limited() {
+    let server = WebServer::bind("127.0.0.1:0", Thresholds::default()).unwrap();
+    let _silent: Vec<_> = (0..32).map(|_| TcpStream::connect(server.local_addr()).unwrap()).collect();
+    // Closed without an answer until the silent ones time out.
+    let mut reader = request(&server, "/");
+    let mut response = String::new();
+    assert!(reader.read_to_string(&mut response).map_or(true, |n| n == 0));
+}